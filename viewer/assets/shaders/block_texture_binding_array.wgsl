@@ -23,6 +23,7 @@
 
 struct MaterialUniforms {
     texture_count: u32,
+    sky_light: f32,
 }
 
 @fragment
@@ -54,6 +55,10 @@ fn fragment(
     if (!is_front) {
         pbr_input.N = -pbr_input.N;
     }
+
+    // Sky light only dims the ambient term, the sun and moon are already
+    // scaled by the day/night cycle
+    pbr_input.diffuse_occlusion = vec3<f32>(material_uniforms.sky_light);
     
     // Alpha discard
     pbr_input.material.base_color = alpha_discard(pbr_input.material, pbr_input.material.base_color);
@@ -0,0 +1,7 @@
+#import bevy_pbr::forward_io::VertexOutput
+
+// World space normals mapped from -1..1 to 0..1, the usual normal map colors
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(normalize(in.world_normal) * 0.5 + 0.5, 1.0);
+}
@@ -1,18 +1,33 @@
+use bevy::diagnostic::FrameTimeDiagnosticsPlugin;
 use bevy::pbr::wireframe::WireframePlugin;
 use bevy::prelude::*;
 use bevy::winit::UpdateMode;
 
 use viewer::block::BuiltBlockID;
 use viewer::built_block_mesh::{get_texture, isotropic_mesh, top_bottom_mesh};
-use viewer::chunk::{BlockTextures, Chunk, SetBlockEvent, WorldPos};
+use viewer::chunk::culling::{self, ChunkCulling};
+use viewer::chunk::{
+    BlockChangeCause, BlockChanged, BlockMaterial, BlockTextures, Chunk,
+    GetBlockEvent, GetRegionEvent, SetBlockEvent, WorldPos,
+};
 use viewer::raycast::RaycastDebugInfo;
 use viewer::simple_control::PlayerCamera;
 use viewer::{atlas_enum::AtlasEnum, block::BlockRegistry, chunk::World};
 use viewer::{bindless_material::BindlessMaterial, chunk::RegenerateMesh};
 
+use viewer::atlas_definition::{Atlas, AtlasLoader};
 use viewer::gpu_fsc::GpuFeatureSupportChecker;
+use viewer::wireframe::NormalsMaterial;
 use viewer::gui_atlas::{self};
-use viewer::{crosshair, debug_screen, raycast, simple_control};
+use viewer::inventory::{self, Inventory};
+use viewer::light::{self, TimeCommand};
+use viewer::entity::dropping_item::{self, ItemPickedUp};
+use viewer::item::mesh::{self as item_mesh, ItemMeshes};
+use viewer::components::texture_override;
+use viewer::{
+    crosshair, debug_screen, hud, mining, particle, raycast, settings,
+    simple_control, sounds,
+};
 
 pub fn main() {
     let mut app = App::new();
@@ -47,39 +62,121 @@ pub fn main() {
         GpuFeatureSupportChecker,
         WireframePlugin::default(),
         MaterialPlugin::<BindlessMaterial>::default(),
-    ));
+        FrameTimeDiagnosticsPlugin::default(),
+        MaterialPlugin::<NormalsMaterial>::default(),
+    ))
+    .init_asset::<Atlas>()
+    .init_asset_loader::<AtlasLoader>();
 
-    app.add_systems(Startup, (setup, debug_screen::setup))
+    app.add_systems(Startup, (setup, debug_screen::setup, sounds::setup, mining::setup, particle::setup, inventory::setup, viewer::wireframe::setup, light::setup_day_night, settings::setup))
         .add_systems(
             Update,
             (
                 input_handler,
+                place_selected_block,
                 simple_control::player_movement_system,
                 simple_control::player_look_system,
                 simple_control::cursor_grab_system,
                 viewer::chunk::handle_set_block_events.run_if(on_event::<SetBlockEvent>),
-                viewer::wireframe::toggle_wireframe
-                    .run_if(on_event::<viewer::wireframe::ToggleWireframe>),
-                viewer::chunk::update_dirty_chunks,
+                viewer::chunk::handle_get_block_events
+                    .run_if(on_event::<GetBlockEvent>)
+                    .after(viewer::chunk::handle_set_block_events),
+                viewer::chunk::handle_get_region_events
+                    .run_if(on_event::<GetRegionEvent>)
+                    .after(viewer::chunk::handle_set_block_events),
+                (
+                    viewer::wireframe::cycle_debug_render_mode,
+                    viewer::wireframe::toggle_wireframe,
+                    viewer::wireframe::apply_wireframe_config,
+                    viewer::wireframe::swap_debug_materials::<BindlessMaterial>,
+                    viewer::wireframe::swap_debug_materials::<StandardMaterial>,
+                )
+                    .chain(),
+                viewer::chunk::send_block_changed_events
+                    .after(viewer::chunk::handle_set_block_events),
+                viewer::chunk::flush_unspawned_chunks
+                    .after(viewer::chunk::handle_set_block_events),
+                viewer::chunk::update_dirty_chunks
+                    .after(viewer::chunk::flush_unspawned_chunks),
+                viewer::chunk::sync_block_material,
                 raycast::update_outline_box,
+                (mining::update_block_breaking, mining::update_crack_overlay)
+                    .chain()
+                    .after(raycast::update_outline_box),
+                sounds::play_block_change_sounds
+                    .after(viewer::chunk::send_block_changed_events),
+                sounds::play_footstep_sounds
+                    .after(simple_control::player_movement_system),
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                particle::spawn_block_break_particles
+                    .after(viewer::chunk::send_block_changed_events),
+                particle::update_block_particles,
+                dropping_item::spawn_block_drops
+                    .after(viewer::chunk::send_block_changed_events),
+                (
+                    dropping_item::update_item_drops,
+                    dropping_item::merge_item_drops,
+                    dropping_item::animate_item_drops,
+                    dropping_item::pickup_item_drops,
+                    inventory::collect_picked_up_items,
+                )
+                    .chain(),
+                item_mesh::build_item_models,
+                (inventory::select_hotbar_slot, inventory::update_hotbar)
+                    .chain()
+                    .after(place_selected_block),
+                (hud::update_stat_icons, hud::update_experience_bar),
+                (
+                    light::time_keyboard_commands,
+                    light::handle_time_commands,
+                    light::advance_time_of_day,
+                    light::update_sun_and_moon,
+                    light::update_sky_light,
+                )
+                    .chain(),
+                (
+                    settings::adjust_view_distance,
+                    settings::apply_render_settings,
+                    settings::save_render_settings,
+                    viewer::chunk::streaming::stream_chunks,
+                    culling::update_visible_chunks,
+                    culling::update_chunk_visibility,
+                )
+                    .chain(),
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                (
+                    debug_screen::toggle_debug_overlay,
+                    debug_screen::update_coordinate_display,
+                )
+                    .chain(),
+                debug_screen::draw_chunk_borders,
             ),
         )
         // .add_systems(PostUpdate, ())
-        .add_systems(FixedUpdate, (debug_screen::update_coordinate_display,));
-    app.insert_resource(Time::<Fixed>::from_hz(10.0));
-    app.insert_resource(BreakCooldown::default());
+        .add_observer(texture_override::observe);
     app.insert_resource(RaycastDebugInfo::default());
+    app.init_resource::<ChunkCulling>();
+    app.init_resource::<ItemMeshes>();
 
     app.add_event::<viewer::wireframe::ToggleWireframe>()
+        .add_event::<viewer::wireframe::SetDebugRenderMode>()
         .add_event::<RegenerateMesh>()
-        .add_event::<SetBlockEvent>();
+        .add_event::<SetBlockEvent>()
+        .add_event::<BlockChanged>()
+        .add_event::<GetBlockEvent>()
+        .add_event::<GetRegionEvent>()
+        .add_event::<ItemPickedUp>()
+        .add_event::<TimeCommand>();
 
-    app.insert_resource(AmbientLight {
-        brightness: 200.0,
-        color: Color::srgb(0.8, 0.9, 1.0),
-        ..default()
-    })
-    .insert_resource(BlockRegistry {
+    app.insert_resource(BlockRegistry {
         grass: top_bottom_mesh(
             get_texture("grass_carried.png"),
             get_texture("dirt.png"),
@@ -94,19 +191,6 @@ pub fn main() {
     app.run();
 }
 
-#[derive(Resource)]
-struct BreakCooldown {
-    timer: Timer,
-}
-
-impl Default for BreakCooldown {
-    fn default() -> Self {
-        Self {
-            timer: Timer::from_seconds(0.3, TimerMode::Once),
-        }
-    }
-}
-
 fn setup(
     breg: Res<BlockRegistry>,
     asset_server: Res<AssetServer>,
@@ -122,6 +206,12 @@ fn setup(
         gui_atlas::IconsAtlas::setup_atlas(&asset_server, &mut texture_atlases);
 
     crosshair::setup(
+        &mut commands,
+        icons_texture_handle.clone(),
+        icons_atlas_layout_handle.clone(),
+    );
+
+    hud::setup(
         &mut commands,
         icons_texture_handle,
         icons_atlas_layout_handle,
@@ -132,65 +222,63 @@ fn setup(
         .map(|&path| asset_server.load(format!("images/blocks/{}", path)))
         .collect();
 
+    let block_material = BlockMaterial::new(&mut materials, &block_textures);
     let mut world = World::new();
 
     set_block_events.write_batch([SetBlockEvent {
         world_pos: WorldPos { x: 0, y: 0, z: 0 },
         block: BuiltBlockID::WoolColoredOrange,
+        cause: BlockChangeCause::Unspecified,
     }]);
 
     viewer::chunk::spawn_chunk(
         &mut world,
         &mut commands,
         &mut meshes,
-        &mut materials,
+        &block_material.0,
         &breg,
         viewer::chunk::ChunkPos {
             x: -1,
             y: -1,
             z: -1,
         },
-        &block_textures,
         Some(BuiltBlockID::Brick),
     );
 
     world.create_chunk_now(
         &mut commands,
         &mut meshes,
-        &mut materials,
+        &block_material.0,
         &breg,
         viewer::chunk::ChunkPos { x: 0, y: -1, z: -1 },
-        &block_textures,
     );
 
     world.create_chunk_now(
         &mut commands,
         &mut meshes,
-        &mut materials,
+        &block_material.0,
         &breg,
         viewer::chunk::ChunkPos { x: -2, y: -1, z: -1 },
-        &block_textures,
     );
 
     world.create_chunk_now(
         &mut commands,
         &mut meshes,
-        &mut materials,
+        &block_material.0,
         &breg,
         viewer::chunk::ChunkPos { x: -1, y: -1, z: 0 },
-        &block_textures,
     );
 
     world.create_chunk_now(
         &mut commands,
         &mut meshes,
-        &mut materials,
+        &block_material.0,
         &breg,
         viewer::chunk::ChunkPos { x: -1, y: -1, z: -2 },
-        &block_textures,
     );
 
     commands.insert_resource(BlockTextures(block_textures));
+    commands.insert_resource(block_material);
     commands.insert_resource(world);
 
     simple_control::setup(
@@ -200,123 +288,74 @@ fn setup(
         Vec3::new(0.0, 2.0, 0.0),
         Some(Vec3::new(2.0, 1.0, -4.0)),
     );
-
-    commands.spawn((
-        DirectionalLight {
-            illuminance: 5000.0,
-            shadows_enabled: true,
-            color: Color::srgb(1.0, 0.95, 0.8),
-            ..default()
-        },
-        Transform::from_rotation(Quat::from_euler(
-            EulerRot::XYZ,
-            -std::f32::consts::PI / 3.0,
-            std::f32::consts::PI / 4.0,
-            0.0,
-        )),
-        bevy::pbr::CascadeShadowConfigBuilder {
-            first_cascade_far_bound: 25.0,
-            maximum_distance: 200.0,
-            ..default()
-        }
-        .build(),
-    ));
-
-    // commands.spawn((
-    //     DirectionalLight {
-    //         illuminance: 2000.0,
-    //         shadows_enabled: false,
-    //         color: Color::srgb(0.7, 0.8, 1.0),
-    //         ..default()
-    //     },
-    //     Transform::from_rotation(Quat::from_euler(
-    //         EulerRot::XYZ,
-    //         std::f32::consts::PI / 6.0,
-    //         -std::f32::consts::PI / 2.0,
-    //         0.0,
-    //     )),
-    // ));
 }
 
 fn input_handler(
     keyboard_input: Res<ButtonInput<KeyCode>>,
-    mouse_input: Res<ButtonInput<MouseButton>>,
-    camera_query: Query<(&GlobalTransform, &Camera), With<PlayerCamera>>,
     mut set_block_events: EventWriter<SetBlockEvent>,
     // mut break_block_writer: EventWriter<BreakBlock>,
-    mut break_cooldown: ResMut<BreakCooldown>,
-    time: Res<Time>,
-    windows: Query<&Window>,
-    world: Res<World>,
-    chunks: Query<&mut Chunk>,
 ) {
-    break_cooldown.timer.tick(time.delta());
-
-    if keyboard_input.just_pressed(KeyCode::KeyG) {
+    // F3 + G toggles chunk borders instead
+    if keyboard_input.just_pressed(KeyCode::KeyG)
+        && !keyboard_input.pressed(KeyCode::F3)
+    {
         set_block_events.write_batch([
             SetBlockEvent {
                 world_pos: WorldPos { x: 1, y: 0, z: 0 },
                 block: BuiltBlockID::Grass,
+                cause: BlockChangeCause::Unspecified,
             },
             SetBlockEvent {
                 world_pos: WorldPos { x: 0, y: 1, z: 0 },
                 block: BuiltBlockID::Dirt,
+                cause: BlockChangeCause::Unspecified,
             },
             SetBlockEvent {
                 world_pos: WorldPos { x: 0, y: 0, z: 1 },
                 block: BuiltBlockID::WoolColoredOrange,
+                cause: BlockChangeCause::Unspecified,
             },
         ]);
     }
+}
 
-    if mouse_input.pressed(MouseButton::Left) && break_cooldown.timer.finished() {
-        if let Ok((camera_transform, camera)) = camera_query.single() {
-            if let Ok(window) = windows.single() {
-                if let Some((ray_origin, ray_direction)) =
-                    raycast::get_camera_ray(camera_transform, camera, window)
-                {
-                    if let Some(hit) = raycast::precise_minecraft_raycast(
-                        ray_origin,
-                        ray_direction,
-                        &world,
-                        &chunks,
-                        20.0,
-                    ) {
-                        set_block_events.write(SetBlockEvent {
-                            world_pos: hit.position,
-                            block: BuiltBlockID::Air,
-                        });
-
-                        println!("破坏方块: {:?} (距离: {:.2})", hit.position, hit.distance);
-
-                        break_cooldown.timer.reset();
-                    }
-                }
-            }
-        }
+fn place_selected_block(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    camera_query: Query<(&GlobalTransform, &Camera), With<PlayerCamera>>,
+    mut set_block_events: EventWriter<SetBlockEvent>,
+    windows: Query<&Window>,
+    world: Res<World>,
+    chunks: Query<&mut Chunk>,
+    mut inventory: ResMut<Inventory>,
+) {
+    if !mouse_input.just_pressed(MouseButton::Right) {
+        return;
     }
+    let Some(block) = inventory.selected_block() else {
+        return;
+    };
 
-    if mouse_input.just_pressed(MouseButton::Right) {
-        if let Ok((camera_transform, camera)) = camera_query.single() {
-            if let Ok(window) = windows.single() {
-                if let Some((ray_origin, ray_direction)) =
-                    raycast::get_camera_ray(camera_transform, camera, window)
-                {
-                    if let Some(hit) = raycast::precise_minecraft_raycast(
-                        ray_origin,
-                        ray_direction,
-                        &world,
-                        &chunks,
-                        20.0,
-                    ) {
-                        if let Some(place_pos) =
-                            raycast::get_adjacent_empty_position(&hit, &world, &chunks)
-                        {
-                            set_block_events.write(SetBlockEvent {
-                                world_pos: place_pos,
-                                block: BuiltBlockID::Brick,
-                            });
-                        }
+    if let Ok((camera_transform, camera)) = camera_query.single() {
+        if let Ok(window) = windows.single() {
+            if let Some((ray_origin, ray_direction)) =
+                raycast::get_camera_ray(camera_transform, camera, window)
+            {
+                if let Some(hit) = raycast::precise_minecraft_raycast(
+                    ray_origin,
+                    ray_direction,
+                    &world,
+                    &chunks,
+                    20.0,
+                ) {
+                    if let Some(place_pos) =
+                        raycast::get_adjacent_empty_position(&hit, &world, &chunks)
+                    {
+                        set_block_events.write(SetBlockEvent {
+                            world_pos: place_pos,
+                            block,
+                            cause: BlockChangeCause::PlayerPlace,
+                        });
+                        inventory.take_selected();
                     }
                 }
             }
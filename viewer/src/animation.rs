@@ -1,6 +1,8 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
-use bevy::ecs::{component::Component, resource::Resource};
+use bevy::ecs::{
+    component::Component, entity::Entity, event::Event, resource::Resource,
+};
 
 #[derive(Clone, Debug)]
 pub enum AnimationConfig {
@@ -9,6 +11,9 @@ pub enum AnimationConfig {
         speed: f32,
         repeat: bool,
         paused: bool,
+        /// Only drive the bones of this mask, see
+        /// [`BoneMasks`](crate::model::mask::BoneMasks)
+        mask: Option<String>,
     },
     Blend {
         animations: Vec<(String, f32)>,
@@ -18,7 +23,30 @@ pub enum AnimationConfig {
     },
 }
 
-#[derive(Component, Clone)]
+/// Where the clip of an [`AnimationConfig::Single`] comes from, parsed
+/// from its `path`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipSource<'a> {
+    /// Asset path with a positional label, `models/villager.gltf#Animation3`.
+    /// Breaks silently when the animations in the file are reordered.
+    Label(&'a str),
+    /// Animation name inside a glTF file, `models/villager.gltf@walk`,
+    /// resolved through `Gltf::named_animations`
+    Named { gltf: &'a str, name: &'a str },
+}
+
+impl<'a> ClipSource<'a> {
+    pub fn parse(path: &'a str) -> Self {
+        match path.split_once('@') {
+            Some((gltf, name)) => ClipSource::Named { gltf, name },
+            None => ClipSource::Label(path),
+        }
+    }
+}
+
+/// Name of the animation a model plays. Changing it cross-fades to the new
+/// animation.
+#[derive(Component, Clone, Debug, PartialEq, Eq)]
 pub struct ModelAnimation(pub String);
 
 impl ModelAnimation {
@@ -27,5 +55,50 @@ impl ModelAnimation {
     }
 }
 
-#[derive(Resource)]
-pub struct AnimationConfigs(pub HashMap<String, AnimationConfig>);
+/// Runtime weights of the animations inside blends, by animation name,
+/// replacing the configured ones. Goes on a model or its animation player.
+#[derive(Component, Clone, Debug, Default)]
+pub struct BlendWeights(pub HashMap<String, f32>);
+
+impl BlendWeights {
+    pub fn set(&mut self, animation: &str, weight: f32) {
+        self.0.insert(animation.to_string(), weight);
+    }
+}
+
+/// Cross-fade a model, or one of its animation players, to another
+/// animation over `fade`
+#[derive(Event, Debug, Clone)]
+pub struct PlayAnimation {
+    pub entity: Entity,
+    pub name: String,
+    pub fade: Duration,
+}
+
+/// Entity type of the model an `AnimationPlayer` belongs to, which its
+/// [`ModelAnimation`] is looked up in
+#[derive(Component, Clone, Debug)]
+pub struct AnimationOwner(pub String);
+
+/// Animation configs by entity type, then by animation name
+#[derive(Resource, Default)]
+pub struct AnimationConfigs(
+    pub HashMap<String, HashMap<String, AnimationConfig>>,
+);
+
+impl AnimationConfigs {
+    pub fn get(
+        &self,
+        entity_type: &str,
+        name: &str,
+    ) -> Option<&AnimationConfig> {
+        self.0.get(entity_type)?.get(name)
+    }
+
+    pub fn model_mut(
+        &mut self,
+        entity_type: &str,
+    ) -> &mut HashMap<String, AnimationConfig> {
+        self.0.entry(entity_type.to_string()).or_default()
+    }
+}
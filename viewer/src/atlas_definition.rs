@@ -0,0 +1,377 @@
+use std::{collections::HashMap, fmt, io};
+
+use bevy::{
+    asset::{io::Reader, Asset, AssetLoader, Handle, LoadContext},
+    math::{URect, UVec2},
+    reflect::TypePath,
+    ui::widget::ImageNode,
+};
+use bevy_image::{Image, TextureAtlas, TextureAtlasLayout};
+use serde::Deserialize;
+
+use crate::region::Region;
+
+/// Texture atlas described by a data file instead of code.
+///
+/// ```ron
+/// (
+///     image: "images/gui/icons.png",
+///     width: 256,
+///     height: 256,
+///     regions: [
+///         (name: "crosshair", x: 0, y: 0, w: 16, h: 16),
+///     ],
+///     grid: Some((
+///         tile_width: 9,
+///         tile_height: 9,
+///         columns: 4,
+///         rows: 1,
+///         offset_x: 16,
+///         names: ["heart_container", "heart_container_blink"],
+///     )),
+/// )
+/// ```
+///
+/// Named regions come first in the layout, followed by the grid tiles in
+/// row-major order.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AtlasDefinition {
+    /// Relative to the assets folder
+    pub image: String,
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub regions: Vec<NamedRegion>,
+    #[serde(default)]
+    pub grid: Option<AtlasGrid>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NamedRegion {
+    pub name: String,
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+/// Evenly sized tiles, optionally separated by `padding`
+#[derive(Debug, Clone, Deserialize)]
+pub struct AtlasGrid {
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub columns: u32,
+    pub rows: u32,
+    #[serde(default)]
+    pub padding: u32,
+    #[serde(default)]
+    pub offset_x: u32,
+    #[serde(default)]
+    pub offset_y: u32,
+    /// Names for the first tiles, the rest are only reachable by index
+    #[serde(default)]
+    pub names: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum AtlasDefinitionError {
+    Io(io::Error),
+    Ron(ron::error::SpannedError),
+    Json(serde_json::Error),
+    OutOfBounds(String),
+}
+
+impl fmt::Display for AtlasDefinitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AtlasDefinitionError::Io(err) => write!(f, "io error: {}", err),
+            AtlasDefinitionError::Ron(err) => write!(f, "ron error: {}", err),
+            AtlasDefinitionError::Json(err) => {
+                write!(f, "json error: {}", err)
+            }
+            AtlasDefinitionError::OutOfBounds(name) => {
+                write!(f, "region '{}' lies outside the image", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AtlasDefinitionError {}
+
+impl From<io::Error> for AtlasDefinitionError {
+    fn from(err: io::Error) -> Self {
+        AtlasDefinitionError::Io(err)
+    }
+}
+
+impl AtlasDefinition {
+    pub fn from_ron(text: &str) -> Result<Self, AtlasDefinitionError> {
+        let definition: Self =
+            ron::from_str(text).map_err(AtlasDefinitionError::Ron)?;
+        definition.validate()?;
+        Ok(definition)
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, AtlasDefinitionError> {
+        let definition: Self =
+            serde_json::from_str(text).map_err(AtlasDefinitionError::Json)?;
+        definition.validate()?;
+        Ok(definition)
+    }
+
+    /// Pick the format from the file extension, RON unless it is `.json`
+    pub fn parse(path: &str, text: &str) -> Result<Self, AtlasDefinitionError> {
+        if path.ends_with(".json") {
+            Self::from_json(text)
+        } else {
+            Self::from_ron(text)
+        }
+    }
+
+    pub fn size(&self) -> UVec2 {
+        UVec2::new(self.width, self.height)
+    }
+
+    /// Every region in layout order, with its name if it has one. Grid
+    /// positions saturate instead of overflowing, leaving it to
+    /// validation to reject them.
+    pub fn regions(&self) -> Vec<(Option<&str>, Region)> {
+        let mut regions: Vec<_> = self
+            .regions
+            .iter()
+            .map(|region| {
+                let NamedRegion { x, y, w, h, .. } = *region;
+                (Some(region.name.as_str()), Region { x, y, w, h })
+            })
+            .collect();
+
+        if let Some(grid) = &self.grid {
+            for row in 0..grid.rows {
+                for column in 0..grid.columns {
+                    let tile = (row * grid.columns + column) as usize;
+                    regions.push((
+                        grid.names.get(tile).map(String::as_str),
+                        Region {
+                            x: grid.offset_x.saturating_add(
+                                column.saturating_mul(
+                                    grid.tile_width
+                                        .saturating_add(grid.padding),
+                                ),
+                            ),
+                            y: grid.offset_y.saturating_add(
+                                row.saturating_mul(
+                                    grid.tile_height
+                                        .saturating_add(grid.padding),
+                                ),
+                            ),
+                            w: grid.tile_width,
+                            h: grid.tile_height,
+                        },
+                    ));
+                }
+            }
+        }
+
+        regions
+    }
+
+    pub fn region(&self, name: &str) -> Option<Region> {
+        self.regions()
+            .into_iter()
+            .find(|(region_name, _)| *region_name == Some(name))
+            .map(|(_, region)| region)
+    }
+
+    pub fn indices(&self) -> HashMap<String, usize> {
+        self.regions()
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, (name, _))| Some((name?.to_string(), index)))
+            .collect()
+    }
+
+    pub fn layout(&self) -> TextureAtlasLayout {
+        let mut layout = TextureAtlasLayout::new_empty(self.size());
+        for (_, region) in self.regions() {
+            layout.add_texture(URect {
+                min: UVec2::new(region.x, region.y),
+                max: UVec2::new(region.x + region.w, region.y + region.h),
+            });
+        }
+        layout
+    }
+
+    /// Every region must lie inside the image. Regions may overlap, e.g. a
+    /// hotbar and the slots inside it. Unnamed regions are reported by
+    /// their layout index.
+    pub fn validate(&self) -> Result<(), AtlasDefinitionError> {
+        let regions = self.regions();
+        let label = |index: usize| {
+            regions[index]
+                .0
+                .map_or_else(|| index.to_string(), str::to_string)
+        };
+
+        for (index, (_, region)) in regions.iter().enumerate() {
+            let inside = region
+                .x
+                .checked_add(region.w)
+                .is_some_and(|right| right <= self.width)
+                && region
+                    .y
+                    .checked_add(region.h)
+                    .is_some_and(|bottom| bottom <= self.height);
+            if !inside {
+                return Err(AtlasDefinitionError::OutOfBounds(label(index)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A loaded [`AtlasDefinition`]: the image, its layout and the index of
+/// every named region
+#[derive(Asset, TypePath, Debug)]
+pub struct Atlas {
+    pub image: Handle<Image>,
+    pub layout: Handle<TextureAtlasLayout>,
+    pub indices: HashMap<String, usize>,
+}
+
+impl Atlas {
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.indices.get(name).copied()
+    }
+
+    pub fn texture_atlas(&self, name: &str) -> Option<TextureAtlas> {
+        Some(TextureAtlas {
+            index: self.index(name)?,
+            layout: self.layout.clone(),
+        })
+    }
+
+    pub fn image_node(&self, name: &str) -> Option<ImageNode> {
+        Some(ImageNode::from_atlas_image(
+            self.image.clone(),
+            self.texture_atlas(name)?,
+        ))
+    }
+}
+
+/// Loads `*.atlas.ron` and `*.atlas.json` files into an [`Atlas`]. The
+/// layout is available under the `layout` label.
+#[derive(Default)]
+pub struct AtlasLoader;
+
+impl AssetLoader for AtlasLoader {
+    type Asset = Atlas;
+    type Settings = ();
+    type Error = AtlasDefinitionError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Atlas, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let text = String::from_utf8_lossy(&bytes);
+
+        let path = load_context.path().to_string_lossy().to_string();
+        let definition = AtlasDefinition::parse(&path, &text)?;
+
+        let layout = load_context
+            .add_labeled_asset("layout".into(), definition.layout());
+
+        Ok(Atlas {
+            image: load_context.load(definition.image.clone()),
+            layout,
+            indices: definition.indices(),
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["atlas.ron", "atlas.json"]
+    }
+}
+
+/// Declare an [`AtlasEnum`](crate::atlas_enum::AtlasEnum) whose regions come
+/// from an atlas definition file under `assets/`. Each variant names a
+/// region of the file; a name missing from the file panics on first use.
+///
+/// ```ignore
+/// atlas_enum! {
+///     pub enum IconsAtlas from "atlases/icons.atlas.ron" {
+///         Crosshair = "crosshair",
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! atlas_enum {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident from $file:literal {
+            $($variant:ident = $region:literal),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(strum_macros::EnumIter, Copy, Clone, Debug, PartialEq, Eq)]
+        $vis enum $name {
+            $($variant),*
+        }
+
+        impl $name {
+            pub fn definition(
+            ) -> &'static $crate::atlas_definition::AtlasDefinition {
+                static DEFINITION: std::sync::OnceLock<
+                    $crate::atlas_definition::AtlasDefinition,
+                > = std::sync::OnceLock::new();
+
+                DEFINITION.get_or_init(|| {
+                    $crate::atlas_definition::AtlasDefinition::parse(
+                        $file,
+                        include_str!(concat!(
+                            env!("CARGO_MANIFEST_DIR"),
+                            "/assets/",
+                            $file
+                        )),
+                    )
+                    .unwrap_or_else(|err| {
+                        panic!("invalid atlas definition {}: {}", $file, err)
+                    })
+                })
+            }
+
+            pub fn region_name(&self) -> &'static str {
+                match self {
+                    $($name::$variant => $region),*
+                }
+            }
+        }
+
+        impl $crate::atlas_enum::AtlasEnum for $name {
+            fn texture_source() -> (&'static str, bevy::math::UVec2) {
+                let definition = Self::definition();
+                (definition.image.as_str(), definition.size())
+            }
+
+            fn get_region(&self) -> $crate::region::Region {
+                Self::definition()
+                    .region(self.region_name())
+                    .unwrap_or_else(|| {
+                        panic!(
+                            "{} has no region '{}'",
+                            $file,
+                            self.region_name()
+                        )
+                    })
+            }
+
+            fn get_index(&self) -> usize {
+                *self as usize
+            }
+        }
+    };
+}
@@ -27,13 +27,25 @@ const SHADER_ASSET_PATH: &str = "shaders/block_texture_binding_array.wgsl";
 #[derive(Clone, Debug)]
 pub struct MaterialUniforms {
     pub texture_count: u32,
+    /// Brightness of ambient light coming from the sky, `0.0..=1.0`
+    pub sky_light: f32,
 }
 
+impl MaterialUniforms {
+    fn to_bytes(&self) -> [u8; 8] {
+        let mut bytes = [0; 8];
+        bytes[..4].copy_from_slice(&self.texture_count.to_ne_bytes());
+        bytes[4..].copy_from_slice(&self.sky_light.to_ne_bytes());
+        bytes
+    }
+}
+
+// Same size as the two 32-bit fields of the WGSL struct
 impl ShaderType for MaterialUniforms {
     type ExtraMetadata = ();
 
     const METADATA: bevy::render::render_resource::encase::private::Metadata<Self::ExtraMetadata> =
-        u32::METADATA;
+        bevy::math::UVec2::METADATA;
 }
 
 #[derive(Asset, TypePath, Debug, Clone)]
@@ -79,7 +91,7 @@ impl AsBindGroup for BindlessMaterial {
         // Create uniform buffer for material uniforms
         let uniform_buffer = render_device.create_buffer_with_data(&BufferInitDescriptor {
             label: Some("material_uniforms_buffer"),
-            contents: &self.uniforms.texture_count.to_ne_bytes(),
+            contents: &self.uniforms.to_bytes(),
             usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
         });
 
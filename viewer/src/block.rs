@@ -1,7 +1,21 @@
 use bevy::{ecs::resource::Resource, render::mesh::Mesh};
 use bevy_meshem::{VoxelMesh, VoxelRegistry};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, strum_macros::EnumCount, strum_macros::FromRepr)]
+use crate::sounds::SoundGroup;
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum_macros::EnumCount,
+    strum_macros::FromRepr,
+    strum_macros::EnumString,
+    strum_macros::IntoStaticStr,
+)]
+#[strum(serialize_all = "snake_case")]
 pub enum BuiltBlockID {
     Air,
     Brick, 
@@ -15,6 +29,39 @@ impl BuiltBlockID {
     pub fn from_repr_or_air(value: usize) -> Self {
         BuiltBlockID::from_repr(value).unwrap_or(BuiltBlockID::Air)
     }
+
+    /// Stable name used when blocks are written to files, e.g. `planks_oak`
+    pub fn name(self) -> &'static str {
+        self.into()
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        name.parse().ok()
+    }
+
+    /// Sounds played when the block is broken, placed or walked on
+    pub fn sound_group(self) -> Option<SoundGroup> {
+        match self {
+            BuiltBlockID::Air => None,
+            BuiltBlockID::Brick => Some(SoundGroup::Stone),
+            BuiltBlockID::Dirt | BuiltBlockID::Grass => Some(SoundGroup::Grass),
+            BuiltBlockID::PlanksOak => Some(SoundGroup::Wood),
+            BuiltBlockID::WoolColoredOrange => Some(SoundGroup::Cloth),
+        }
+    }
+
+    /// Texture in `images/blocks` standing for the whole block, used for
+    /// break particles and item icons
+    pub fn texture(self) -> Option<&'static str> {
+        match self {
+            BuiltBlockID::Air => None,
+            BuiltBlockID::Brick => Some("brick.png"),
+            BuiltBlockID::Dirt => Some("dirt.png"),
+            BuiltBlockID::Grass => Some("grass_side_carried.png"),
+            BuiltBlockID::PlanksOak => Some("planks_oak.png"),
+            BuiltBlockID::WoolColoredOrange => Some("wool_colored_orange.png"),
+        }
+    }
 }
 
 #[derive(Resource)]
@@ -26,6 +73,25 @@ pub struct BlockRegistry {
     pub wool_colored_orange: Mesh,
 }
 
+impl BlockRegistry {
+    /// Minecraft-style hardness, `0.0` breaks instantly
+    pub fn hardness(&self, voxel: BuiltBlockID) -> f32 {
+        match voxel {
+            BuiltBlockID::Air => 0.0,
+            BuiltBlockID::Brick => 2.0,
+            BuiltBlockID::Dirt => 0.5,
+            BuiltBlockID::Grass => 0.6,
+            BuiltBlockID::PlanksOak => 2.0,
+            BuiltBlockID::WoolColoredOrange => 0.8,
+        }
+    }
+
+    /// Seconds needed to break `voxel` by hand
+    pub fn break_time(&self, voxel: BuiltBlockID) -> f32 {
+        self.hardness(voxel) * 1.5
+    }
+}
+
 impl VoxelRegistry for BlockRegistry {
     type Voxel = BuiltBlockID;
 
@@ -1,17 +1,24 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, sync::mpsc::Sender};
 
 use bevy::{
     asset::{Assets, Handle},
     ecs::{
+        change_detection::DetectChanges,
         component::Component,
-        entity::Entity,
-        event::{Event, EventReader},
+        entity::{Entities, Entity},
+        event::{Event, EventReader, EventWriter},
+        query::With,
         resource::Resource,
-        system::{Commands, Query, Res, ResMut},
+        system::{Commands, Query, Res, ResMut, SystemParam},
     },
+    log::debug,
+    math::UVec3,
     pbr::MeshMaterial3d,
     platform::collections::HashSet,
-    render::mesh::{Mesh, Mesh3d},
+    render::{
+        mesh::{Mesh, Mesh3d, MeshAabb},
+        primitives::Aabb,
+    },
     transform::components::Transform,
 };
 use bevy_image::Image;
@@ -25,7 +32,15 @@ use crate::{
     block::{BlockRegistry, BuiltBlockID},
 };
 
-const SIZE: usize = 8;
+pub mod culling;
+pub mod streaming;
+
+use culling::FaceConnectivity;
+
+/// Edge length of a chunk in blocks
+pub const SIZE: usize = 8;
+/// Most blocks a single [`GetRegionEvent`] can ask for, 256 × 64 × 256
+pub const MAX_REGION_VOLUME: u64 = 1 << 22;
 const CHUNK_LEN: usize = SIZE * SIZE * SIZE;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -83,17 +98,73 @@ impl ChunkPos {
 
 #[derive(Component)]
 pub struct Chunk {
-    #[allow(dead_code)]
     pos: ChunkPos,
     meta: MeshMD<BuiltBlockID>,
     grid: [BuiltBlockID; CHUNK_LEN],
+    connectivity: FaceConnectivity,
     dirty: bool,
 }
 
+impl Chunk {
+    /// `world_pos` must lie inside this chunk
+    pub fn get_block(&self, world_pos: WorldPos) -> BuiltBlockID {
+        self.grid[world_pos.to_local_index()]
+    }
+
+    pub fn pos(&self) -> ChunkPos {
+        self.pos
+    }
+
+    /// Waiting to be re-meshed by `update_dirty_chunks`
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Only air. Empty chunks have no mesh or material.
+    pub fn is_empty(&self) -> bool {
+        self.grid.iter().all(|&block| block == BuiltBlockID::Air)
+    }
+
+    /// As of the last meshing
+    pub fn connectivity(&self) -> FaceConnectivity {
+        self.connectivity
+    }
+}
+
+/// Result of reading a block. Unlike [`World::get_block`], a position in a
+/// chunk that does not exist is reported as `Unloaded` rather than `Air`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockLookup {
+    Loaded(BuiltBlockID),
+    Unloaded,
+}
+
+impl BlockLookup {
+    pub fn block(self) -> Option<BuiltBlockID> {
+        match self {
+            BlockLookup::Loaded(block) => Some(block),
+            BlockLookup::Unloaded => None,
+        }
+    }
+
+    pub fn is_loaded(self) -> bool {
+        matches!(self, BlockLookup::Loaded(_))
+    }
+}
+
 #[derive(Resource)]
 pub struct World {
     chunks: HashMap<ChunkPos, Entity>,
     loaded_chunks: HashSet<ChunkPos>,
+    /// Changes not yet sent as [`BlockChanged`] events
+    pending_changes: Vec<BlockChanged>,
+    /// Grids of chunks spawned through `Commands` that haven't been filled
+    /// in yet, taking the blocks written to them until
+    /// [`flush_unspawned_chunks`] copies them over
+    unspawned: HashMap<Entity, Box<[BuiltBlockID; CHUNK_LEN]>>,
+    /// Grids of chunks unloaded by [`streaming::stream_chunks`], put back
+    /// when they are loaded again
+    unloaded: HashMap<ChunkPos, Box<[BuiltBlockID; CHUNK_LEN]>>,
 }
 
 impl World {
@@ -101,6 +172,9 @@ impl World {
         Self {
             chunks: HashMap::new(),
             loaded_chunks: HashSet::new(),
+            pending_changes: Vec::new(),
+            unspawned: HashMap::new(),
+            unloaded: HashMap::new(),
         }
     }
 
@@ -109,66 +183,153 @@ impl World {
         &mut self,
         world_pos: WorldPos,
         block: BuiltBlockID,
+        cause: BlockChangeCause,
         commands: &mut Commands,
         chunks: &mut Query<&mut Chunk>,
         meshes: &mut ResMut<Assets<Mesh>>,
-        materials: &mut ResMut<Assets<BindlessMaterial>>,
+        material: &Handle<BindlessMaterial>,
         breg: &BlockRegistry,
-        block_textures: &[Handle<Image>],
     ) -> bool {
         let chunk_pos = world_pos.to_chunk_pos();
 
         if !self.chunks.contains_key(&chunk_pos) {
-            self.create_chunk_now(
-                commands,
-                meshes,
-                materials,
-                breg,
-                chunk_pos,
-                block_textures,
-            );
+            self.create_chunk_now(commands, meshes, material, breg, chunk_pos);
         }
 
-        self.set_block_in_chunk(world_pos, block, chunks)
+        self.set_block_in_chunk(world_pos, block, cause, chunks)
     }
 
     pub fn create_chunk_now(
         &mut self,
         commands: &mut Commands,
         meshes: &mut ResMut<Assets<Mesh>>,
-        materials: &mut ResMut<Assets<BindlessMaterial>>,
+        material: &Handle<BindlessMaterial>,
         breg: &BlockRegistry,
         chunk_pos: ChunkPos,
-        block_textures: &[Handle<Image>],
     ) {
-        spawn_chunk(
-            self,
-            commands,
-            meshes,
-            materials,
-            breg,
-            chunk_pos,
-            block_textures,
-            None,
-        );
+        if self.unloaded.contains_key(&chunk_pos) {
+            self.load_chunk(commands, meshes, material, breg, chunk_pos);
+        } else {
+            spawn_chunk(
+                self, commands, meshes, material, breg, chunk_pos, None,
+            );
+        }
+    }
+
+    /// Despawn a chunk, keeping its blocks until [`World::load_chunk`]
+    pub fn unload_chunk(
+        &mut self,
+        commands: &mut Commands,
+        chunks: &Query<&Chunk>,
+        chunk_pos: ChunkPos,
+    ) {
+        let Some(entity) = self.chunks.remove(&chunk_pos) else {
+            return;
+        };
+        self.loaded_chunks.remove(&chunk_pos);
+        let grid = match self.unspawned.remove(&entity) {
+            Some(grid) => Some(grid),
+            None => chunks.get(entity).ok().map(|chunk| Box::new(chunk.grid)),
+        };
+        if let Some(grid) = grid {
+            self.unloaded.insert(chunk_pos, grid);
+        }
+        commands.entity(entity).despawn();
     }
 
+    /// Spawn a chunk unloaded by [`World::unload_chunk`] again, with the
+    /// blocks it had
+    pub fn load_chunk(
+        &mut self,
+        commands: &mut Commands,
+        meshes: &mut ResMut<Assets<Mesh>>,
+        material: &Handle<BindlessMaterial>,
+        breg: &BlockRegistry,
+        chunk_pos: ChunkPos,
+    ) {
+        if let Some(grid) = self.unloaded.remove(&chunk_pos) {
+            spawn_grid(
+                self, commands, meshes, material, breg, chunk_pos, *grid,
+            );
+        }
+    }
+
+    /// Chunks unloaded by [`World::unload_chunk`] and not loaded since
+    pub fn unloaded_chunks(&self) -> impl Iterator<Item = ChunkPos> + '_ {
+        self.unloaded.keys().copied()
+    }
+
+    /// Setting a block to what it already is leaves the chunk clean and
+    /// records no change. Chunks spawned this frame take the block once
+    /// they exist.
     pub fn set_block_in_chunk(
         &mut self,
         world_pos: WorldPos,
         block: BuiltBlockID,
+        cause: BlockChangeCause,
         chunks: &mut Query<&mut Chunk>,
     ) -> bool {
-        let chunk_pos = world_pos.to_chunk_pos();
-        if let Some(&chunk_entity) = self.chunks.get(&chunk_pos) {
-            if let Ok(mut chunk) = chunks.get_mut(chunk_entity) {
-                let index = world_pos.to_local_index();
+        let Some(&chunk_entity) = self.chunks.get(&world_pos.to_chunk_pos())
+        else {
+            return false;
+        };
+        let index = world_pos.to_local_index();
+
+        // The pending grid wins over the chunk, it replaces it once flushed
+        let old = if let Some(grid) = self.unspawned.get_mut(&chunk_entity) {
+            std::mem::replace(&mut grid[index], block)
+        } else if let Ok(mut chunk) = chunks.get_mut(chunk_entity) {
+            let old = chunk.grid[index];
+            if old != block {
                 chunk.grid[index] = block;
                 chunk.dirty = true;
-                return true;
             }
+            old
+        } else {
+            return false;
+        };
+
+        if old != block {
+            self.pending_changes.push(BlockChanged {
+                pos: world_pos,
+                old,
+                new: block,
+                cause,
+            });
         }
-        false
+        true
+    }
+
+    /// Copy the blocks written to chunks before they existed into them.
+    /// Grids of chunks still waiting on their spawn command are kept, the
+    /// ones of chunks despawned in the meantime are dropped.
+    pub fn flush_unspawned(
+        &mut self,
+        chunks: &mut Query<&mut Chunk>,
+        entities: &Entities,
+    ) {
+        self.unspawned.retain(|&entity, grid| {
+            let Ok(mut chunk) = chunks.get_mut(entity) else {
+                return entities.contains(entity);
+            };
+            if chunk.grid != **grid {
+                chunk.grid = **grid;
+                chunk.dirty = true;
+            }
+            false
+        });
+    }
+
+    pub fn chunk_entity(&self, chunk_pos: ChunkPos) -> Option<Entity> {
+        self.chunks.get(&chunk_pos).copied()
+    }
+
+    pub fn is_loaded(&self, chunk_pos: ChunkPos) -> bool {
+        self.loaded_chunks.contains(&chunk_pos)
+    }
+
+    pub fn loaded_chunk_count(&self) -> usize {
+        self.loaded_chunks.len()
     }
 
     pub fn get_block(
@@ -178,13 +339,26 @@ impl World {
     ) -> BuiltBlockID {
         let chunk_pos = world_pos.to_chunk_pos();
         if let Some(&chunk_entity) = self.chunks.get(&chunk_pos) {
+            if let Some(block) = self.pending_block(chunk_entity, world_pos) {
+                return block;
+            }
             if let Ok(chunk) = chunks.get(chunk_entity) {
-                let index = world_pos.to_local_index();
-                return chunk.grid[index];
+                return chunk.get_block(world_pos);
             }
         }
         BuiltBlockID::Air
     }
+
+    /// The block written to a chunk that hasn't been flushed yet, which
+    /// takes precedence over the chunk's own grid
+    fn pending_block(
+        &self,
+        chunk_entity: Entity,
+        world_pos: WorldPos,
+    ) -> Option<BuiltBlockID> {
+        let grid = self.unspawned.get(&chunk_entity)?;
+        Some(grid[world_pos.to_local_index()])
+    }
 }
 
 #[derive(Event, Default)]
@@ -194,119 +368,455 @@ pub struct RegenerateMesh;
 pub struct SetBlockEvent {
     pub world_pos: WorldPos,
     pub block: BuiltBlockID,
+    pub cause: BlockChangeCause,
 }
 
+/// Why a block changed, so listeners can react differently to e.g. a player
+/// breaking a block and a schematic being pasted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlockChangeCause {
+    #[default]
+    Unspecified,
+    PlayerBreak,
+    PlayerPlace,
+    Schematic,
+    /// A chunk was spawned already filled
+    Generated,
+}
+
+/// Sent once for every block whose value actually changed, by
+/// [`send_block_changed_events`].
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockChanged {
+    pub pos: WorldPos,
+    pub old: BuiltBlockID,
+    pub new: BuiltBlockID,
+    pub cause: BlockChangeCause,
+}
+
+/// Ask for a single block; answered by [`handle_get_block_events`].
+/// Without a sender the result is only logged.
 #[derive(Event)]
 pub struct GetBlockEvent {
     pub world_pos: WorldPos,
-    pub response_sender: Option<std::sync::mpsc::Sender<BuiltBlockID>>,
+    pub response_sender: Option<Sender<BlockResponse>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockResponse {
+    pub world_pos: WorldPos,
+    pub block: BlockLookup,
+}
+
+/// Ask for every block between `min` and `max` (both inclusive);
+/// answered by [`handle_get_region_events`].
+#[derive(Event)]
+pub struct GetRegionEvent {
+    pub min: WorldPos,
+    pub max: WorldPos,
+    pub response_sender: Option<Sender<Result<RegionResponse, RegionTooLarge>>>,
+}
+
+/// A [`GetRegionEvent`] spanning more than [`MAX_REGION_VOLUME`] blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegionTooLarge {
+    pub min: WorldPos,
+    pub max: WorldPos,
+}
+
+impl fmt::Display for RegionTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "region {:?}..{:?} holds more than {} blocks",
+            self.min, self.max, MAX_REGION_VOLUME
+        )
+    }
+}
+
+impl std::error::Error for RegionTooLarge {}
+
+/// `blocks` is ordered `x + z * size.x + y * size.x * size.z`, starting at
+/// `min`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionResponse {
+    pub min: WorldPos,
+    pub size: UVec3,
+    pub blocks: Vec<BlockLookup>,
+}
+
+impl RegionResponse {
+    pub fn get(&self, world_pos: WorldPos) -> Option<BlockLookup> {
+        let x = u32::try_from(world_pos.x - self.min.x).ok()?;
+        let y = u32::try_from(world_pos.y - self.min.y).ok()?;
+        let z = u32::try_from(world_pos.z - self.min.z).ok()?;
+        if x >= self.size.x || y >= self.size.y || z >= self.size.z {
+            return None;
+        }
+        let index = x + z * self.size.x + y * self.size.x * self.size.z;
+        self.blocks.get(index as usize).copied()
+    }
+}
+
+/// Read-only access to blocks for systems that don't need to modify the
+/// world. Can run in parallel with other readers.
+#[derive(SystemParam)]
+pub struct WorldReader<'w, 's> {
+    world: Res<'w, World>,
+    chunks: Query<'w, 's, &'static Chunk>,
+}
+
+impl WorldReader<'_, '_> {
+    /// Agrees with [`World::get_block`], including blocks written to
+    /// chunks that haven't been flushed yet
+    pub fn get_block(&self, world_pos: WorldPos) -> BlockLookup {
+        let Some(entity) = self.world.chunk_entity(world_pos.to_chunk_pos())
+        else {
+            return BlockLookup::Unloaded;
+        };
+        if let Some(block) = self.world.pending_block(entity, world_pos) {
+            return BlockLookup::Loaded(block);
+        }
+        self.chunks
+            .get(entity)
+            .map_or(BlockLookup::Unloaded, |chunk| {
+                BlockLookup::Loaded(chunk.get_block(world_pos))
+            })
+    }
+
+    /// Fails without allocating when the region holds more than
+    /// [`MAX_REGION_VOLUME`] blocks
+    pub fn get_region(
+        &self,
+        min: WorldPos,
+        max: WorldPos,
+    ) -> Result<RegionResponse, RegionTooLarge> {
+        let lo = WorldPos {
+            x: min.x.min(max.x),
+            y: min.y.min(max.y),
+            z: min.z.min(max.z),
+        };
+        let extent = |a: i32, b: i32| u64::from(a.abs_diff(b)) + 1;
+        let (x, y, z) = (
+            extent(min.x, max.x),
+            extent(min.y, max.y),
+            extent(min.z, max.z),
+        );
+        let volume = x
+            .checked_mul(y)
+            .and_then(|area| area.checked_mul(z))
+            .filter(|&volume| volume <= MAX_REGION_VOLUME)
+            .ok_or(RegionTooLarge { min, max })?;
+        // Every side is at most the volume, well within `u32`
+        let size = UVec3::new(x as u32, y as u32, z as u32);
+
+        let mut blocks = Vec::with_capacity(volume as usize);
+        for y in 0..size.y as i32 {
+            for z in 0..size.z as i32 {
+                for x in 0..size.x as i32 {
+                    blocks.push(self.get_block(WorldPos {
+                        x: lo.x + x,
+                        y: lo.y + y,
+                        z: lo.z + z,
+                    }));
+                }
+            }
+        }
+
+        Ok(RegionResponse {
+            min: lo,
+            size,
+            blocks,
+        })
+    }
 }
 
 #[derive(Resource)]
 pub struct BlockTextures(pub Vec<Handle<Image>>);
 
+/// The one material every chunk is drawn with, so they can be batched.
+/// Built from [`BlockTextures`] and kept in sync by
+/// [`sync_block_material`].
+#[derive(Resource)]
+pub struct BlockMaterial(pub Handle<BindlessMaterial>);
+
+impl BlockMaterial {
+    pub fn new(
+        materials: &mut Assets<BindlessMaterial>,
+        textures: &[Handle<Image>],
+    ) -> Self {
+        Self(materials.add(chunk_material(textures)))
+    }
+}
+
 pub fn handle_set_block_events(
     mut set_block_events: EventReader<SetBlockEvent>,
     mut world: ResMut<World>,
     mut commands: Commands,
     mut chunks: Query<&mut Chunk>,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<BindlessMaterial>>,
     breg: Res<BlockRegistry>,
-    block_textures: Res<BlockTextures>,
+    block_material: Res<BlockMaterial>,
 ) {
     for event in set_block_events.read() {
         world.set_block(
             event.world_pos,
             event.block,
+            event.cause,
             &mut commands,
             &mut chunks,
             &mut meshes,
-            &mut materials,
+            &block_material.0,
             &breg,
-            &block_textures.0,
         );
     }
 }
 
+/// Fill chunks spawned since the last run with the blocks written to them
+/// meanwhile. Schedule after the systems that set blocks and before
+/// [`update_dirty_chunks`], without a run condition.
+pub fn flush_unspawned_chunks(
+    mut world: ResMut<World>,
+    mut chunks: Query<&mut Chunk>,
+    entities: &Entities,
+) {
+    // Keep `World` unchanged for change detection when there is no work
+    if world.unspawned.is_empty() {
+        return;
+    }
+    world.flush_unspawned(&mut chunks, entities);
+}
+
+/// Send the changes recorded by [`World::set_block_in_chunk`] and
+/// [`spawn_chunk`] since the last run. Schedule after every system that
+/// modifies blocks.
+pub fn send_block_changed_events(
+    mut world: ResMut<World>,
+    mut block_changed_events: EventWriter<BlockChanged>,
+) {
+    if !world.pending_changes.is_empty() {
+        block_changed_events.write_batch(world.pending_changes.drain(..));
+    }
+}
+
+pub fn handle_get_block_events(
+    mut get_block_events: EventReader<GetBlockEvent>,
+    reader: WorldReader,
+) {
+    for event in get_block_events.read() {
+        let response = BlockResponse {
+            world_pos: event.world_pos,
+            block: reader.get_block(event.world_pos),
+        };
+
+        match &event.response_sender {
+            Some(sender) => {
+                if sender.send(response).is_err() {
+                    debug!("GetBlockEvent receiver dropped: {:?}", response);
+                }
+            }
+            None => debug!("GetBlockEvent: {:?}", response),
+        }
+    }
+}
+
+pub fn handle_get_region_events(
+    mut get_region_events: EventReader<GetRegionEvent>,
+    reader: WorldReader,
+) {
+    for event in get_region_events.read() {
+        let response = reader.get_region(event.min, event.max);
+
+        match &event.response_sender {
+            Some(sender) => {
+                if sender.send(response).is_err() {
+                    debug!(
+                        "GetRegionEvent receiver dropped: {:?}..{:?}",
+                        event.min, event.max
+                    );
+                }
+            }
+            None => match response {
+                Ok(response) => debug!(
+                    "GetRegionEvent: {:?}..{:?} ({} blocks)",
+                    event.min,
+                    event.max,
+                    response.blocks.len()
+                ),
+                Err(err) => debug!("GetRegionEvent: {}", err),
+            },
+        }
+    }
+}
+
 pub fn update_dirty_chunks(
-    mut chunks: Query<(&mut Chunk, &Mesh3d)>,
+    mut commands: Commands,
+    mut chunks: Query<(Entity, &mut Chunk, Option<&Mesh3d>)>,
     breg: Res<BlockRegistry>,
+    block_material: Res<BlockMaterial>,
     mut meshes: ResMut<Assets<Mesh>>,
 ) {
     let breg = breg.into_inner();
 
-    for (mut chunk, mesh3d) in chunks.iter_mut() {
-        if chunk.dirty {
-            if let Some(mesh) = meshes.get_mut(&mesh3d.0) {
-                let (new_mesh, new_metadata) = mesh_grid(
-                    (SIZE, SIZE, SIZE),
-                    &[],
-                    &chunk.grid,
-                    breg,
-                    MeshingAlgorithm::Culling,
-                    None,
-                )
-                .unwrap();
-
-                *mesh = new_mesh;
-                chunk.meta = new_metadata;
-                chunk.dirty = false;
+    for (entity, mut chunk, mesh3d) in chunks.iter_mut() {
+        if !chunk.dirty {
+            continue;
+        }
+
+        let (new_mesh, new_metadata) = mesh_grid(
+            (SIZE, SIZE, SIZE),
+            &[],
+            &chunk.grid,
+            breg,
+            MeshingAlgorithm::Culling,
+            None,
+        )
+        .unwrap();
+
+        chunk.meta = new_metadata;
+        chunk.connectivity = FaceConnectivity::compute(&chunk.grid);
+        chunk.dirty = false;
+
+        if chunk.is_empty() {
+            if let Some(mesh3d) = mesh3d {
+                meshes.remove(&mesh3d.0);
+                commands.entity(entity).remove::<(
+                    Mesh3d,
+                    MeshMaterial3d<BindlessMaterial>,
+                    Aabb,
+                )>();
             }
+            continue;
+        }
+
+        // Bevy only computes the bounds once, they go stale when the mesh
+        // asset is replaced
+        let aabb = new_mesh.compute_aabb();
+        match mesh3d.and_then(|mesh3d| meshes.get_mut(&mesh3d.0)) {
+            Some(mesh) => *mesh = new_mesh,
+            None => {
+                commands.entity(entity).insert((
+                    Mesh3d(meshes.add(new_mesh)),
+                    MeshMaterial3d(block_material.0.clone()),
+                ));
+            }
+        }
+        if let Some(aabb) = aabb {
+            commands.entity(entity).insert(aabb);
         }
     }
 }
 
+/// Hot-swap the block material: a new texture set is written into the
+/// shared material, a new [`BlockMaterial`] handle is pushed to every chunk
+pub fn sync_block_material(
+    block_textures: Res<BlockTextures>,
+    block_material: Res<BlockMaterial>,
+    mut materials: ResMut<Assets<BindlessMaterial>>,
+    mut chunks: Query<&mut MeshMaterial3d<BindlessMaterial>, With<Chunk>>,
+) {
+    if block_textures.is_changed() && !block_textures.is_added() {
+        if let Some(material) = materials.get_mut(&block_material.0) {
+            material.uniforms.texture_count = block_textures.0.len() as u32;
+            material.textures = block_textures.0.clone();
+        }
+    }
+
+    if block_material.is_changed() && !block_material.is_added() {
+        for mut material in &mut chunks {
+            if material.0 != block_material.0 {
+                material.0 = block_material.0.clone();
+            }
+        }
+    }
+}
+
+fn chunk_material(textures: &[Handle<Image>]) -> BindlessMaterial {
+    BindlessMaterial {
+        uniforms: MaterialUniforms {
+            texture_count: textures.len() as u32,
+            sky_light: 1.0,
+        },
+        textures: textures.to_vec(),
+    }
+}
+
 pub fn spawn_chunk(
     world: &mut World,
     commands: &mut Commands,
     meshes: &mut ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<BindlessMaterial>>,
+    material: &Handle<BindlessMaterial>,
     breg: &BlockRegistry,
     chunk_pos: ChunkPos,
-    textures: &[Handle<Image>],
     fill_with: Option<BuiltBlockID>,
 ) {
     let fill_block = fill_with.unwrap_or(BuiltBlockID::Air);
-    let grid = vec![fill_block; CHUNK_LEN];
-    let grid_array: [BuiltBlockID; CHUNK_LEN] = grid.try_into().unwrap();
+    // A fresh chunk replaces whatever was unloaded there
+    world.unloaded.remove(&chunk_pos);
+    spawn_grid(
+        world,
+        commands,
+        meshes,
+        material,
+        breg,
+        chunk_pos,
+        [fill_block; CHUNK_LEN],
+    );
+
+    if fill_block != BuiltBlockID::Air {
+        world.pending_changes.extend((0..SIZE).flat_map(|y| {
+            (0..SIZE).flat_map(move |z| {
+                (0..SIZE).map(move |x| BlockChanged {
+                    pos: chunk_pos.to_world_pos(x, y, z),
+                    old: BuiltBlockID::Air,
+                    new: fill_block,
+                    cause: BlockChangeCause::Generated,
+                })
+            })
+        }));
+    }
+}
+
+fn spawn_grid(
+    world: &mut World,
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    material: &Handle<BindlessMaterial>,
+    breg: &BlockRegistry,
+    chunk_pos: ChunkPos,
+    grid: [BuiltBlockID; CHUNK_LEN],
+) {
     let dims: Dimensions = (SIZE, SIZE, SIZE);
 
-    let (culled_mesh, metadata) = mesh_grid(
-        dims,
-        &[],
-        &grid_array,
-        breg,
-        MeshingAlgorithm::Culling,
-        None,
-    )
-    .unwrap();
-
-    let culled_mesh_handle: Handle<Mesh> = meshes.add(culled_mesh);
-
-    let chunk_entity = commands
-        .spawn((
-            Mesh3d(culled_mesh_handle),
-            MeshMaterial3d(materials.add(BindlessMaterial {
-                uniforms: MaterialUniforms {
-                    texture_count: textures.len() as u32,
-                },
-                textures: textures.to_vec(),
-            })),
-            Chunk {
-                pos: chunk_pos,
-                meta: metadata,
-                grid: grid_array,
-                dirty: false,
-            },
-            Transform::from_xyz(
-                chunk_pos.x as f32 * SIZE as f32,
-                chunk_pos.y as f32 * SIZE as f32,
-                chunk_pos.z as f32 * SIZE as f32,
-            ),
-        ))
-        .id();
+    let (culled_mesh, metadata) =
+        mesh_grid(dims, &[], &grid, breg, MeshingAlgorithm::Culling, None)
+            .unwrap();
+
+    let mut chunk_commands = commands.spawn((
+        Chunk {
+            pos: chunk_pos,
+            meta: metadata,
+            connectivity: FaceConnectivity::compute(&grid),
+            grid,
+            dirty: false,
+        },
+        Transform::from_xyz(
+            chunk_pos.x as f32 * SIZE as f32,
+            chunk_pos.y as f32 * SIZE as f32,
+            chunk_pos.z as f32 * SIZE as f32,
+        ),
+    ));
+
+    // Empty chunks only hold data until a block is placed in them
+    if grid.iter().any(|&block| block != BuiltBlockID::Air) {
+        chunk_commands.insert((
+            Mesh3d(meshes.add(culled_mesh)),
+            MeshMaterial3d(material.clone()),
+        ));
+    }
+    let chunk_entity = chunk_commands.id();
 
     world.chunks.insert(chunk_pos, chunk_entity);
     world.loaded_chunks.insert(chunk_pos);
+    world.unspawned.insert(chunk_entity, Box::new(grid));
 }
@@ -0,0 +1,280 @@
+use std::collections::VecDeque;
+
+use bevy::{
+    ecs::{
+        change_detection::{DetectChanges, DetectChangesMut},
+        query::{Changed, With},
+        resource::Resource,
+        system::{Query, Res, ResMut},
+    },
+    platform::collections::HashSet,
+    render::view::Visibility,
+    transform::components::GlobalTransform,
+};
+
+use crate::{
+    block::BuiltBlockID, settings::RenderSettings, simple_control::PlayerCamera,
+};
+
+use super::{Chunk, ChunkPos, World, WorldPos, CHUNK_LEN, SIZE};
+
+/// One of the six sides of a chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkFace {
+    NegX,
+    PosX,
+    NegY,
+    PosY,
+    NegZ,
+    PosZ,
+}
+
+impl ChunkFace {
+    pub const ALL: [ChunkFace; 6] = [
+        ChunkFace::NegX,
+        ChunkFace::PosX,
+        ChunkFace::NegY,
+        ChunkFace::PosY,
+        ChunkFace::NegZ,
+        ChunkFace::PosZ,
+    ];
+
+    pub fn opposite(self) -> Self {
+        match self {
+            ChunkFace::NegX => ChunkFace::PosX,
+            ChunkFace::PosX => ChunkFace::NegX,
+            ChunkFace::NegY => ChunkFace::PosY,
+            ChunkFace::PosY => ChunkFace::NegY,
+            ChunkFace::NegZ => ChunkFace::PosZ,
+            ChunkFace::PosZ => ChunkFace::NegZ,
+        }
+    }
+
+    /// The chunk on the other side of this face
+    pub fn neighbour(self, pos: ChunkPos) -> ChunkPos {
+        let (x, y, z) = match self {
+            ChunkFace::NegX => (-1, 0, 0),
+            ChunkFace::PosX => (1, 0, 0),
+            ChunkFace::NegY => (0, -1, 0),
+            ChunkFace::PosY => (0, 1, 0),
+            ChunkFace::NegZ => (0, 0, -1),
+            ChunkFace::PosZ => (0, 0, 1),
+        };
+        ChunkPos {
+            x: pos.x + x,
+            y: pos.y + y,
+            z: pos.z + z,
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+
+    /// Faces of the chunk touched by the block at a local position
+    fn touched_by(x: usize, y: usize, z: usize) -> u8 {
+        let last = SIZE - 1;
+        [
+            (x == 0, ChunkFace::NegX),
+            (x == last, ChunkFace::PosX),
+            (y == 0, ChunkFace::NegY),
+            (y == last, ChunkFace::PosY),
+            (z == 0, ChunkFace::NegZ),
+            (z == last, ChunkFace::PosZ),
+        ]
+        .into_iter()
+        .filter(|(touches, _)| *touches)
+        .fold(0, |faces, (_, face)| faces | face.bit())
+    }
+}
+
+/// Which faces of a chunk can see each other through its non-opaque
+/// blocks, one bit per pair of faces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FaceConnectivity(u64);
+
+impl FaceConnectivity {
+    /// Nothing blocks the view, e.g. an empty or unloaded chunk
+    pub const OPEN: FaceConnectivity = FaceConnectivity(u64::MAX);
+    /// Completely solid
+    pub const CLOSED: FaceConnectivity = FaceConnectivity(0);
+
+    pub fn connects(self, a: ChunkFace, b: ChunkFace) -> bool {
+        self.0 & Self::pair_bit(a, b) != 0
+    }
+
+    /// Flood fill every pocket of non-opaque blocks and connect all faces
+    /// that pocket touches
+    pub fn compute(grid: &[BuiltBlockID; CHUNK_LEN]) -> Self {
+        if grid.iter().all(|&block| block == BuiltBlockID::Air) {
+            return Self::OPEN;
+        }
+
+        let mut connectivity = Self::CLOSED;
+        let mut visited = [false; CHUNK_LEN];
+        let mut queue = Vec::new();
+
+        for start in 0..CHUNK_LEN {
+            if visited[start] || grid[start] != BuiltBlockID::Air {
+                continue;
+            }
+            visited[start] = true;
+            queue.push(start);
+            let mut faces = 0;
+
+            while let Some(index) = queue.pop() {
+                let (x, y, z) =
+                    (index % SIZE, index / (SIZE * SIZE), index / SIZE % SIZE);
+                faces |= ChunkFace::touched_by(x, y, z);
+
+                let neighbours = [
+                    (x > 0).then(|| index - 1),
+                    (x < SIZE - 1).then(|| index + 1),
+                    (y > 0).then(|| index - SIZE * SIZE),
+                    (y < SIZE - 1).then(|| index + SIZE * SIZE),
+                    (z > 0).then(|| index - SIZE),
+                    (z < SIZE - 1).then(|| index + SIZE),
+                ];
+                for next in neighbours.into_iter().flatten() {
+                    if !visited[next] && grid[next] == BuiltBlockID::Air {
+                        visited[next] = true;
+                        queue.push(next);
+                    }
+                }
+            }
+
+            connectivity.connect_all(faces);
+        }
+
+        connectivity
+    }
+
+    fn connect_all(&mut self, faces: u8) {
+        for a in ChunkFace::ALL {
+            for b in ChunkFace::ALL {
+                if faces & a.bit() != 0 && faces & b.bit() != 0 {
+                    self.0 |= Self::pair_bit(a, b);
+                }
+            }
+        }
+    }
+
+    fn pair_bit(a: ChunkFace, b: ChunkFace) -> u64 {
+        1 << (a as u64 * 6 + b as u64)
+    }
+}
+
+/// Chunks that survived the last culling pass
+#[derive(Resource, Default)]
+pub struct ChunkCulling {
+    pub visible: HashSet<ChunkPos>,
+    camera_chunk: Option<ChunkPos>,
+}
+
+/// Chunk the camera is in
+pub fn camera_chunk(camera: &GlobalTransform) -> ChunkPos {
+    let pos = camera.translation();
+    WorldPos {
+        x: pos.x.round() as i32,
+        y: pos.y.round() as i32,
+        z: pos.z.round() as i32,
+    }
+    .to_chunk_pos()
+}
+
+/// Cave culling: walk outwards from the camera's chunk, only leaving a
+/// chunk through a face that is connected to the face it was entered
+/// through, and never turning back towards the camera. Chunks the walk
+/// cannot reach are hidden even if they are inside the view frustum.
+pub fn update_visible_chunks(
+    world: Res<World>,
+    settings: Res<RenderSettings>,
+    camera_query: Query<&GlobalTransform, With<PlayerCamera>>,
+    chunks: Query<&Chunk>,
+    changed_chunks: Query<(), Changed<Chunk>>,
+    mut culling: ResMut<ChunkCulling>,
+) {
+    let Ok(camera_transform) = camera_query.single() else {
+        return;
+    };
+    let camera_chunk = camera_chunk(camera_transform);
+
+    if culling.camera_chunk == Some(camera_chunk)
+        && changed_chunks.is_empty()
+        && !settings.is_changed()
+        && !world.is_changed()
+    {
+        return;
+    }
+
+    let connectivity = |pos: ChunkPos| {
+        world
+            .chunk_entity(pos)
+            .and_then(|entity| chunks.get(entity).ok())
+            .map_or(FaceConnectivity::OPEN, Chunk::connectivity)
+    };
+
+    // Unloaded space is open, but there is no point walking above or below
+    // every loaded chunk
+    let (min_y, max_y) = chunks.iter().fold(
+        (camera_chunk.y, camera_chunk.y),
+        |(min_y, max_y), chunk| {
+            (min_y.min(chunk.pos.y), max_y.max(chunk.pos.y))
+        },
+    );
+    let view_distance = settings.view_distance as i32;
+    let in_range = |pos: ChunkPos| {
+        (pos.x - camera_chunk.x).abs() <= view_distance
+            && (pos.z - camera_chunk.z).abs() <= view_distance
+            && (min_y..=max_y).contains(&pos.y)
+    };
+
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::new();
+    visited.insert(camera_chunk);
+    queue.push_back((camera_chunk, None, 0u8));
+
+    while let Some((pos, entered_through, directions)) = queue.pop_front() {
+        let connectivity = connectivity(pos);
+
+        for face in ChunkFace::ALL {
+            if directions & face.opposite().bit() != 0 {
+                continue;
+            }
+            if let Some(entered_through) = entered_through {
+                if !connectivity.connects(entered_through, face) {
+                    continue;
+                }
+            }
+            let next = face.neighbour(pos);
+            if !in_range(next) || !visited.insert(next) {
+                continue;
+            }
+            queue.push_back((
+                next,
+                Some(face.opposite()),
+                directions | face.bit(),
+            ));
+        }
+    }
+
+    visited.retain(|&pos| world.is_loaded(pos));
+    culling.visible = visited;
+    culling.camera_chunk = Some(camera_chunk);
+}
+
+/// Hide chunks outside the view distance or cut off by
+/// [`update_visible_chunks`]
+pub fn update_chunk_visibility(
+    culling: Res<ChunkCulling>,
+    mut chunks: Query<(&Chunk, &mut Visibility)>,
+) {
+    for (chunk, mut visibility) in &mut chunks {
+        let wanted = if culling.visible.contains(&chunk.pos) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        visibility.set_if_neq(wanted);
+    }
+}
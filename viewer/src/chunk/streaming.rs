@@ -0,0 +1,82 @@
+//! Chunks beyond the view distance are unloaded and loaded again once the
+//! camera comes back, keeping the blocks they had.
+
+use bevy::{
+    asset::Assets,
+    ecs::{
+        query::With,
+        system::{Commands, Query, Res, ResMut, SystemParam},
+    },
+    render::mesh::Mesh,
+    transform::components::GlobalTransform,
+};
+
+use crate::{
+    block::BlockRegistry, settings::RenderSettings,
+    simple_control::PlayerCamera,
+};
+
+use super::{culling::camera_chunk, BlockMaterial, Chunk, ChunkPos, World};
+
+/// Chunks kept loaded past the view distance, so walking back and forth
+/// over a chunk border doesn't unload and load a row every step
+pub const UNLOAD_MARGIN: u32 = 1;
+
+/// What loading a chunk again takes
+#[derive(SystemParam)]
+pub struct ChunkAssets<'w> {
+    meshes: ResMut<'w, Assets<Mesh>>,
+    breg: Res<'w, BlockRegistry>,
+    block_material: Res<'w, BlockMaterial>,
+}
+
+/// Horizontal distance in chunks, the same square the culling walks
+fn distance(a: ChunkPos, b: ChunkPos) -> u32 {
+    a.x.abs_diff(b.x).max(a.z.abs_diff(b.z))
+}
+
+/// Unload chunks further than the view distance plus [`UNLOAD_MARGIN`]
+/// from the camera and load unloaded ones back within the view distance
+pub fn stream_chunks(
+    mut world: ResMut<World>,
+    settings: Res<RenderSettings>,
+    camera_query: Query<&GlobalTransform, With<PlayerCamera>>,
+    chunks: Query<&Chunk>,
+    mut commands: Commands,
+    mut assets: ChunkAssets,
+) {
+    let Ok(camera_transform) = camera_query.single() else {
+        return;
+    };
+    let camera_chunk = camera_chunk(camera_transform);
+
+    // Looked up first so `World` only counts as changed with work to do
+    let unload: Vec<_> = world
+        .loaded_chunks
+        .iter()
+        .copied()
+        .filter(|&pos| {
+            distance(pos, camera_chunk) > settings.view_distance + UNLOAD_MARGIN
+        })
+        .collect();
+    let load: Vec<_> = world
+        .unloaded_chunks()
+        .filter(|&pos| distance(pos, camera_chunk) <= settings.view_distance)
+        .collect();
+    if unload.is_empty() && load.is_empty() {
+        return;
+    }
+
+    for pos in unload {
+        world.unload_chunk(&mut commands, &chunks, pos);
+    }
+    for pos in load {
+        world.load_chunk(
+            &mut commands,
+            &mut assets.meshes,
+            &assets.block_material.0,
+            &assets.breg,
+            pos,
+        );
+    }
+}
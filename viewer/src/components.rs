@@ -1,2 +1,3 @@
 
 pub mod texture_override;
+pub mod player_stats;
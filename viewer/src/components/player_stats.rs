@@ -0,0 +1,51 @@
+use bevy::ecs::{bundle::Bundle, component::Component};
+
+/// Health in half hearts
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Health {
+    pub current: u32,
+    pub max: u32,
+}
+
+/// Food level in half drumsticks
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hunger {
+    pub current: u32,
+    pub max: u32,
+}
+
+/// Armor points, each icon holds two
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Armor(pub u32);
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Default)]
+pub struct Experience {
+    pub level: u32,
+    /// `0.0..1.0` towards the next level
+    pub progress: f32,
+}
+
+#[derive(Bundle)]
+pub struct PlayerStats {
+    pub health: Health,
+    pub hunger: Hunger,
+    pub armor: Armor,
+    pub experience: Experience,
+}
+
+impl Default for PlayerStats {
+    fn default() -> Self {
+        Self {
+            health: Health {
+                current: 20,
+                max: 20,
+            },
+            hunger: Hunger {
+                current: 20,
+                max: 20,
+            },
+            armor: Armor(0),
+            experience: Experience::default(),
+        }
+    }
+}
@@ -1,45 +1,293 @@
 use bevy::{
-    color::Color, ecs::{component::Component, query::With, system::{Commands, Query}}, render::camera::Camera, text::{TextColor, TextFont}, transform::components::GlobalTransform, ui::{
-        widget::Text, AlignItems, FlexDirection, JustifyContent, Node, PositionType, UiRect, Val,
-    }
+    asset::Assets,
+    color::Color,
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::With,
+        resource::Resource,
+        system::{Commands, Local, Query, Res, ResMut, SystemParam},
+    },
+    gizmos::gizmos::Gizmos,
+    input::{keyboard::KeyCode, ButtonInput},
+    math::Vec3,
+    render::{
+        mesh::{Mesh, Mesh3d},
+        view::Visibility,
+    },
+    text::{TextColor, TextFont},
+    transform::components::{GlobalTransform, Transform},
+    ui::{
+        widget::Text, AlignItems, BackgroundColor, FlexDirection,
+        JustifyContent, Node, PositionType, UiRect, Val,
+    },
+};
+
+use crate::{
+    chunk::{culling::ChunkCulling, Chunk, World, WorldPos, WorldReader, SIZE},
+    raycast::RaycastDebugInfo,
+    simple_control::PlayerCamera,
 };
 
+/// F3 toggles the overlay, F3 + G the chunk borders
+#[derive(Resource)]
+pub struct DebugOverlay {
+    pub visible: bool,
+    pub chunk_borders: bool,
+}
+
+impl Default for DebugOverlay {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            chunk_borders: false,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct DebugOverlayRoot;
+
 #[derive(Component)]
 pub struct CoordinateDisplay;
 
+/// Everything the overlay reports about the loaded world
+#[derive(SystemParam)]
+pub struct WorldStats<'w, 's> {
+    world: Res<'w, World>,
+    reader: WorldReader<'w, 's>,
+    chunk_meshes: Query<'w, 's, (&'static Chunk, Option<&'static Mesh3d>)>,
+    culling: Res<'w, ChunkCulling>,
+    meshes: Res<'w, Assets<Mesh>>,
+    entities: Query<'w, 's, Entity>,
+}
+
 pub fn setup(mut commands: Commands) {
+    commands.insert_resource(DebugOverlay::default());
+
     commands
-        .spawn(Node {
-            width: Val::Percent(100.0),
-            height: Val::Percent(100.0),
-            position_type: PositionType::Absolute,
-            flex_direction: FlexDirection::Column,
-            justify_content: JustifyContent::FlexStart,
-            align_items: AlignItems::FlexStart,
-            padding: UiRect::all(Val::Px(20.0)),
-            ..bevy::utils::default()
-        })
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::FlexStart,
+                align_items: AlignItems::FlexStart,
+                padding: UiRect::all(Val::Px(20.0)),
+                ..bevy::utils::default()
+            },
+            DebugOverlayRoot,
+        ))
         .with_children(|parent| {
             parent.spawn((
                 Text::new("X: 0.0, Y: 0.0, Z: 0.0"),
                 TextFont {
-                    font_size: 20.0,
+                    font_size: 16.0,
                     ..bevy::utils::default()
                 },
                 TextColor(Color::WHITE),
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.4)),
                 CoordinateDisplay,
             ));
         });
 }
 
+/// Like Minecraft, the overlay toggles when F3 is released, unless it was
+/// used in a combination such as F3 + G
+pub fn toggle_debug_overlay(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut combination_used: Local<bool>,
+    mut overlay: ResMut<DebugOverlay>,
+    mut roots: Query<&mut Visibility, With<DebugOverlayRoot>>,
+) {
+    if keys.pressed(KeyCode::F3) && keys.just_pressed(KeyCode::KeyG) {
+        overlay.chunk_borders = !overlay.chunk_borders;
+        *combination_used = true;
+    }
+    if keys.just_released(KeyCode::F3) {
+        if !*combination_used {
+            overlay.visible = !overlay.visible;
+        }
+        *combination_used = false;
+    }
+
+    for mut visibility in &mut roots {
+        *visibility = if overlay.visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
 pub fn update_coordinate_display(
-    camera_query: Query<&GlobalTransform, With<Camera>>,
+    overlay: Res<DebugOverlay>,
+    diagnostics: Res<DiagnosticsStore>,
+    raycast: Res<RaycastDebugInfo>,
+    stats: WorldStats,
+    camera_query: Query<&GlobalTransform, With<PlayerCamera>>,
     mut text_query: Query<&mut Text, With<CoordinateDisplay>>,
 ) {
-    if let Ok(camera_transform) = camera_query.single() {
-        if let Ok(mut text) = text_query.single_mut() {
-            let pos = camera_transform.translation();
-            text.0 = format!("X: {:.1}, Y: {:.1}, Z: {:.1}", pos.x, pos.y, pos.z);
+    if !overlay.visible {
+        return;
+    }
+    let Ok(camera_transform) = camera_query.single() else {
+        return;
+    };
+    let Ok(mut text) = text_query.single_mut() else {
+        return;
+    };
+
+    let fps = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+        .unwrap_or(0.0);
+    let frame_time = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FRAME_TIME)
+        .and_then(|frame_time| frame_time.smoothed())
+        .unwrap_or(0.0);
+
+    let pos = camera_transform.translation();
+    let block = WorldPos {
+        x: pos.x.round() as i32,
+        y: pos.y.round() as i32,
+        z: pos.z.round() as i32,
+    };
+    let chunk = block.to_chunk_pos();
+    let (local_x, local_y, local_z) = block.to_local_pos();
+
+    let forward = camera_transform.forward();
+    let yaw = (-forward.x).atan2(-forward.z).to_degrees();
+    let pitch = forward.y.asin().to_degrees();
+
+    let target = match raycast.last_hit {
+        Some(hit) => {
+            let block = stats
+                .reader
+                .get_block(hit)
+                .block()
+                .map_or("unloaded", |block| block.name());
+            let face = raycast.hit_face_normal.map_or("?", face_name);
+            format!("{} at {} {} {}, face {}", block, hit.x, hit.y, hit.z, face)
+        }
+        None => "nothing".to_string(),
+    };
+
+    let mut dirty = 0;
+    let mut vertices = 0;
+    for (chunk, mesh3d) in &stats.chunk_meshes {
+        if chunk.is_dirty() {
+            dirty += 1;
+        }
+        if let Some(mesh) = mesh3d.and_then(|mesh3d| stats.meshes.get(&mesh3d.0))
+        {
+            vertices += mesh.count_vertices();
+        }
+    }
+
+    text.0 = format!(
+        "{:.0} fps ({:.2} ms)\n\
+         XYZ: {:.3} / {:.3} / {:.3}\n\
+         Block: {} {} {}\n\
+         Chunk: {} {} {} in {} {} {}\n\
+         Facing: {} (yaw {:.1} / pitch {:.1})\n\
+         Looking at: {}\n\
+         Chunks: {} loaded, {} visible, {} waiting for meshing\n\
+         Vertices: {}\n\
+         Entities: {} ({} chunks)",
+        fps,
+        frame_time,
+        pos.x,
+        pos.y,
+        pos.z,
+        block.x,
+        block.y,
+        block.z,
+        local_x,
+        local_y,
+        local_z,
+        chunk.x,
+        chunk.y,
+        chunk.z,
+        facing(*forward),
+        yaw,
+        pitch,
+        target,
+        stats.world.loaded_chunk_count(),
+        stats.culling.visible.len(),
+        dirty,
+        vertices,
+        stats.entities.iter().len(),
+        stats.chunk_meshes.iter().len(),
+    );
+}
+
+pub fn draw_chunk_borders(
+    overlay: Res<DebugOverlay>,
+    mut gizmos: Gizmos,
+    chunks: Query<&Chunk>,
+    camera_query: Query<&GlobalTransform, With<PlayerCamera>>,
+) {
+    if !overlay.chunk_borders {
+        return;
+    }
+
+    let current = camera_query.single().ok().map(|transform| {
+        let pos = transform.translation();
+        WorldPos {
+            x: pos.x.round() as i32,
+            y: pos.y.round() as i32,
+            z: pos.z.round() as i32,
         }
+        .to_chunk_pos()
+    });
+
+    let size = SIZE as f32;
+    for chunk in &chunks {
+        let pos = chunk.pos();
+        // Blocks are centered on integer coordinates
+        let center = Vec3::new(pos.x as f32, pos.y as f32, pos.z as f32) * size
+            + Vec3::splat(size / 2.0 - 0.5);
+        let color = if Some(pos) == current {
+            Color::srgb(1.0, 1.0, 0.0)
+        } else {
+            Color::srgb(0.2, 0.4, 1.0)
+        };
+
+        gizmos.cuboid(
+            Transform::from_translation(center).with_scale(Vec3::splat(size)),
+            color,
+        );
     }
 }
+
+/// Minecraft convention: north is -Z, east is +X
+fn facing(forward: Vec3) -> &'static str {
+    if forward.x.abs() > forward.z.abs() {
+        if forward.x > 0.0 {
+            "east (+X)"
+        } else {
+            "west (-X)"
+        }
+    } else if forward.z > 0.0 {
+        "south (+Z)"
+    } else {
+        "north (-Z)"
+    }
+}
+
+fn face_name(normal: Vec3) -> &'static str {
+    [
+        (Vec3::Y, "up"),
+        (Vec3::NEG_Y, "down"),
+        (Vec3::NEG_Z, "north"),
+        (Vec3::Z, "south"),
+        (Vec3::X, "east"),
+        (Vec3::NEG_X, "west"),
+    ]
+    .into_iter()
+    .find(|(direction, _)| direction.distance(normal) < 0.01)
+    .map_or("?", |(_, name)| name)
+}
@@ -1,2 +1,5 @@
 
 pub mod dropping_item;
+pub mod equipment;
+pub mod item_frame;
+pub mod riding;
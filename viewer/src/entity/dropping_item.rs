@@ -1,24 +1,313 @@
-use bevy::asset::{AssetServer, Handle};
-use bevy_image::Image;
+use std::collections::HashMap;
 
-use crate::model::ModelData;
+use bevy::{
+    asset::AssetServer,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::{Event, EventReader, EventWriter},
+        hierarchy::ChildOf,
+        query::{With, Without},
+        system::{Commands, Query, Res},
+    },
+    math::{Quat, Vec3, Vec3Swizzles},
+    render::view::Visibility,
+    time::Time,
+    transform::components::Transform,
+};
+use rand::Rng;
 
-type ItemID = &'static str;
+use crate::{
+    block::BuiltBlockID,
+    chunk::{BlockChangeCause, BlockChanged, WorldPos, WorldReader},
+    inventory::Inventory,
+    item::{mesh::ItemModel, Item, MAX_STACK_SIZE},
+    simple_control::{Player, PlayerCamera, PLAYER_EYE_HEIGHT},
+};
 
-pub struct DroppingItem {
-    pub item: ItemID,
+/// Blocks per second squared
+const GRAVITY: f32 = 16.0;
+/// Fraction of the vertical speed kept when hitting the ground
+const BOUNCE: f32 = 0.4;
+/// Slower bounces than this come to rest
+const REST_SPEED: f32 = 1.0;
+const GROUND_FRICTION: f32 = 6.0;
+const AIR_DRAG: f32 = 0.5;
+
+const MERGE_RADIUS: f32 = 0.5;
+const PICKUP_RADIUS: f32 = 1.0;
+/// Freshly dropped items can't be picked up right away
+const PICKUP_DELAY: f32 = 0.5;
+/// Items left lying around for this long disappear
+const DESPAWN_AGE: f32 = 300.0;
+
+const MODEL_SCALE: f32 = 0.35;
+const BOB_HEIGHT: f32 = 0.08;
+const SPIN_SPEED: f32 = 1.5;
+
+/// A stack of items lying in the world. The transform is the bottom center
+/// of the item; the bobbing model is a child with [`ItemDropModel`].
+#[derive(Component)]
+pub struct ItemDrop {
+    pub item: Item,
+    pub count: u32,
+    pub velocity: Vec3,
+    /// Seconds since the item was dropped
+    pub age: f32,
+}
+
+#[derive(Component)]
+pub struct ItemDropModel;
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ItemPickedUp {
+    pub item: Item,
+    pub count: u32,
 }
 
-impl ModelData for DroppingItem {
-    fn entity_type() -> &'static str {
-        "dropping_item"
+pub fn spawn_item_drop(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    item: Item,
+    count: u32,
+    position: Vec3,
+    velocity: Vec3,
+) -> Entity {
+    commands
+        .spawn((
+            ItemDrop {
+                item,
+                count,
+                velocity,
+                age: 0.0,
+            },
+            Transform::from_translation(position),
+            Visibility::default(),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Transform::from_scale(Vec3::splat(MODEL_SCALE)),
+                ItemModel::new(item, asset_server),
+                ItemDropModel,
+            ));
+        })
+        .id()
+}
+
+pub fn spawn_block_drops(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut block_changed_events: EventReader<BlockChanged>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for event in block_changed_events.read() {
+        if event.cause != BlockChangeCause::PlayerBreak
+            || event.old == BuiltBlockID::Air
+        {
+            continue;
+        }
+
+        let velocity = Vec3::new(
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(2.0..3.0),
+            rng.gen_range(-1.0..1.0),
+        );
+
+        spawn_item_drop(
+            &mut commands,
+            &asset_server,
+            Item::Block(event.old),
+            1,
+            block_center(event.pos) - Vec3::Y * 0.25,
+            velocity,
+        );
     }
+}
+
+pub fn update_item_drops(
+    mut commands: Commands,
+    time: Res<Time>,
+    reader: WorldReader,
+    mut drops: Query<(Entity, &mut ItemDrop, &mut Transform)>,
+) {
+    let dt = time.delta_secs();
+    let is_solid = |point: Vec3| {
+        reader
+            .get_block(block_at(point))
+            .block()
+            .is_some_and(|block| block != BuiltBlockID::Air)
+    };
+
+    for (entity, mut drop, mut transform) in &mut drops {
+        drop.age += dt;
+        if drop.age > DESPAWN_AGE {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        drop.velocity.y -= GRAVITY * dt;
+
+        let current = transform.translation;
+        let mut next = current + drop.velocity * dt;
+
+        // Walls stop horizontal movement on their axis
+        let probe_y = current.y + 0.05;
+        if is_solid(Vec3::new(next.x, probe_y, current.z)) {
+            next.x = current.x;
+            drop.velocity.x = 0.0;
+        }
+        if is_solid(Vec3::new(next.x, probe_y, next.z)) {
+            next.z = current.z;
+            drop.velocity.z = 0.0;
+        }
 
-    fn model_path() -> &'static str {
-        "models/dropping.gltf"
+        if drop.velocity.y < 0.0 && is_solid(next) {
+            next.y = block_at(next).y as f32 + 0.5;
+            drop.velocity.y = -drop.velocity.y * BOUNCE;
+            if drop.velocity.y < REST_SPEED {
+                drop.velocity.y = 0.0;
+            }
+        } else if drop.velocity.y > 0.0 && is_solid(next + Vec3::Y * 0.25) {
+            next.y = current.y;
+            drop.velocity.y = 0.0;
+        }
+
+        let on_ground = is_solid(next - Vec3::Y * 0.01);
+        let damping = if on_ground { GROUND_FRICTION } else { AIR_DRAG };
+        let decay = (-damping * dt).exp();
+        drop.velocity.x *= decay;
+        drop.velocity.z *= decay;
+
+        transform.translation = next;
     }
+}
+
+/// Combine identical nearby stacks into the older one
+pub fn merge_item_drops(
+    mut commands: Commands,
+    mut drops: Query<(Entity, &mut ItemDrop, &Transform)>,
+) {
+    let mut stacks: Vec<_> = drops
+        .iter()
+        .map(|(entity, drop, transform)| {
+            (
+                entity,
+                drop.item,
+                drop.count,
+                drop.age,
+                transform.translation,
+            )
+        })
+        .collect();
+    stacks.sort_by(|a, b| b.3.total_cmp(&a.3));
+
+    for i in 0..stacks.len() {
+        for j in (i + 1)..stacks.len() {
+            let (_, item, count, _, position) = stacks[i];
+            let (other, other_item, other_count, _, other_position) = stacks[j];
+
+            if count == 0
+                || other_count == 0
+                || item != other_item
+                || count + other_count > MAX_STACK_SIZE
+                || position.distance(other_position) > MERGE_RADIUS
+            {
+                continue;
+            }
+
+            stacks[i].2 += other_count;
+            stacks[j].2 = 0;
+            commands.entity(other).despawn();
+        }
+    }
+
+    for (entity, _, count, _, _) in stacks {
+        if count > 0 {
+            if let Ok((_, mut drop, _)) = drops.get_mut(entity) {
+                if drop.count != count {
+                    drop.count = count;
+                }
+            }
+        }
+    }
+}
+
+pub fn animate_item_drops(
+    drops: Query<&ItemDrop>,
+    mut models: Query<(&ChildOf, &mut Transform), With<ItemDropModel>>,
+) {
+    for (child_of, mut transform) in &mut models {
+        let Ok(drop) = drops.get(child_of.parent()) else {
+            continue;
+        };
+
+        transform.translation.y =
+            BOB_HEIGHT * (1.0 + (drop.age * 2.0).sin()) + MODEL_SCALE / 2.0;
+        transform.rotation = Quat::from_rotation_y(drop.age * SPIN_SPEED);
+    }
+}
+
+/// Items near the player are picked up as long as the inventory has room,
+/// leaving whatever doesn't fit on the ground
+pub fn pickup_item_drops(
+    mut commands: Commands,
+    player_query: Query<&Transform, (With<Player>, Without<PlayerCamera>)>,
+    mut drops: Query<(Entity, &mut ItemDrop, &Transform), Without<Player>>,
+    inventory: Option<Res<Inventory>>,
+    mut picked_up_events: EventWriter<ItemPickedUp>,
+) {
+    let Ok(player_transform) = player_query.single() else {
+        return;
+    };
+
+    let eyes = player_transform.translation;
+    let feet = eyes - Vec3::Y * PLAYER_EYE_HEIGHT;
+    let mut taken: HashMap<Item, u32> = HashMap::new();
+
+    for (entity, mut drop, transform) in &mut drops {
+        let position = transform.translation;
+        let horizontal = (position - eyes).xz().length();
+
+        if drop.age < PICKUP_DELAY
+            || horizontal > PICKUP_RADIUS
+            || position.y < feet.y - PICKUP_RADIUS
+            || position.y > eyes.y + PICKUP_RADIUS
+        {
+            continue;
+        }
+
+        let taken = taken.entry(drop.item).or_default();
+        let room = inventory.as_ref().map_or(drop.count, |inventory| {
+            inventory.room_for(drop.item).saturating_sub(*taken)
+        });
+        let count = drop.count.min(room);
+        if count == 0 {
+            continue;
+        }
+        *taken += count;
+
+        picked_up_events.write(ItemPickedUp {
+            item: drop.item,
+            count,
+        });
+
+        if count == drop.count {
+            commands.entity(entity).despawn();
+        } else {
+            drop.count -= count;
+        }
+    }
+}
+
+fn block_center(world_pos: WorldPos) -> Vec3 {
+    Vec3::new(world_pos.x as f32, world_pos.y as f32, world_pos.z as f32)
+}
 
-    fn texture(&self, asset_server: &AssetServer) -> Option<Handle<Image>> {
-        Some(asset_server.load(format!("images/items/{}.png", self.item)))
+fn block_at(point: Vec3) -> WorldPos {
+    WorldPos {
+        x: point.x.round() as i32,
+        y: point.y.round() as i32,
+        z: point.z.round() as i32,
     }
 }
@@ -0,0 +1,351 @@
+use std::{collections::HashMap, f32::consts::FRAC_PI_2};
+
+use bevy::{
+    asset::{AssetServer, Assets, Handle},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        hierarchy::{ChildOf, Children},
+        query::{Added, Changed, Or, With},
+        removal_detection::RemovedComponents,
+        resource::Resource,
+        system::{Commands, Query, Res, ResMut, SystemParam},
+    },
+    math::{Quat, Vec3},
+    pbr::{MeshMaterial3d, StandardMaterial},
+    render::{
+        alpha::AlphaMode,
+        mesh::{Mesh, Mesh3d, MeshAabb},
+    },
+    transform::components::Transform,
+};
+use serde::Deserialize;
+
+use crate::{
+    item::{mesh::ItemModel, Item},
+    model::{
+        assembly::{Attachment, Bones, Joints},
+        manifest::{Model, ModelManifest, ModelManifests},
+    },
+};
+
+/// Size of a held item, in blocks
+const HELD_ITEM_SCALE: f32 = 0.625;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EquipmentSlot {
+    Head,
+    Chest,
+    Legs,
+    Feet,
+    MainHand,
+    OffHand,
+}
+
+impl EquipmentSlot {
+    pub const ARMOR: [EquipmentSlot; 4] = [
+        EquipmentSlot::Head,
+        EquipmentSlot::Chest,
+        EquipmentSlot::Legs,
+        EquipmentSlot::Feet,
+    ];
+
+    /// Joint a held item is attached to
+    pub fn hand_joint(self) -> Option<&'static str> {
+        match self {
+            EquipmentSlot::MainHand => Some("right_hand"),
+            EquipmentSlot::OffHand => Some("left_hand"),
+            _ => None,
+        }
+    }
+
+    /// Item name ending of the armor worn in this slot
+    fn armor_suffix(self) -> Option<&'static str> {
+        match self {
+            EquipmentSlot::Head => Some("_helmet"),
+            EquipmentSlot::Chest => Some("_chestplate"),
+            EquipmentSlot::Legs => Some("_leggings"),
+            EquipmentSlot::Feet => Some("_boots"),
+            _ => None,
+        }
+    }
+
+    /// How far armor stands off the body, in pixels
+    fn inflate(self) -> f32 {
+        match self {
+            EquipmentSlot::Legs => 0.5,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Items worn and held by a model. Armor covers the bones the model's
+/// manifest lists for its slot, held items go to the hand joints.
+#[derive(Component, Debug, Clone, Default, PartialEq)]
+pub struct Equipment {
+    slots: HashMap<EquipmentSlot, Item>,
+}
+
+impl Equipment {
+    pub fn with(mut self, slot: EquipmentSlot, item: impl Into<Item>) -> Self {
+        self.set(slot, item);
+        self
+    }
+
+    pub fn set(&mut self, slot: EquipmentSlot, item: impl Into<Item>) {
+        self.slots.insert(slot, item.into());
+    }
+
+    pub fn clear(&mut self, slot: EquipmentSlot) -> Option<Item> {
+        self.slots.remove(&slot)
+    }
+
+    pub fn get(&self, slot: EquipmentSlot) -> Option<Item> {
+        self.slots.get(&slot).copied()
+    }
+}
+
+/// Armor texture of an item worn in `slot`, `None` if it is not armor for
+/// that slot. Leggings use the second layer like Minecraft's.
+///
+/// No armor textures are shipped. Copy Minecraft's
+/// `textures/models/armor/{material}_layer_{1,2}.png` to
+/// `assets/images/entity/armor/` to see worn armor.
+pub fn armor_texture_path(item: Item, slot: EquipmentSlot) -> Option<String> {
+    let Item::Named(name) = item else {
+        return None;
+    };
+    let material = name.strip_suffix(slot.armor_suffix()?)?;
+    let layer = if slot == EquipmentSlot::Legs { 2 } else { 1 };
+    Some(format!(
+        "images/entity/armor/{}_layer_{}.png",
+        material, layer
+    ))
+}
+
+/// Entities spawned to show a model's [`Equipment`]
+#[derive(Component, Debug, Default)]
+pub struct EquipmentPieces(Vec<Entity>);
+
+impl EquipmentPieces {
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.0.iter().copied()
+    }
+
+    fn despawn(&mut self, commands: &mut Commands) {
+        for piece in self.0.drain(..) {
+            if let Ok(mut piece) = commands.get_entity(piece) {
+                piece.despawn();
+            }
+        }
+    }
+}
+
+/// Material of every armor texture used so far, by path
+#[derive(Resource, Default)]
+pub struct ArmorMaterials(HashMap<String, Handle<StandardMaterial>>);
+
+impl ArmorMaterials {
+    pub fn get(&self, path: &str) -> Option<Handle<StandardMaterial>> {
+        self.0.get(path).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Marks an armor mesh or held item holder
+#[derive(Component, Debug)]
+pub struct EquipmentPiece;
+
+/// Models whose equipment needs rebuilding
+type EquipmentChanges<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, &'static Equipment, &'static Model),
+    (With<Joints>, Or<(Changed<Equipment>, Added<Joints>)>),
+>;
+
+/// Assets armor and held items are built from
+#[derive(SystemParam)]
+pub struct EquipmentAssets<'w> {
+    asset_server: Res<'w, AssetServer>,
+    meshes: Res<'w, Assets<Mesh>>,
+    materials: ResMut<'w, Assets<StandardMaterial>>,
+    armor_materials: ResMut<'w, ArmorMaterials>,
+}
+
+impl EquipmentAssets<'_> {
+    fn armor_material(&mut self, path: String) -> Handle<StandardMaterial> {
+        if let Some(material) = self.armor_materials.get(&path) {
+            return material;
+        }
+        let material = self.materials.add(StandardMaterial {
+            base_color_texture: Some(self.asset_server.load(&path)),
+            alpha_mode: AlphaMode::Mask(0.5),
+            ..StandardMaterial::default()
+        });
+        self.armor_materials.0.insert(path, material.clone());
+        material
+    }
+}
+
+/// Walks the parts of spawned models armor goes over
+#[derive(SystemParam)]
+pub struct ModelParts<'w, 's> {
+    bones: Bones<'w, 's>,
+    children: Query<'w, 's, &'static Children>,
+    meshes: Query<'w, 's, &'static Mesh3d>,
+    pieces: Query<'w, 's, (), With<EquipmentPiece>>,
+}
+
+/// Rebuild the armor and held items of models whose [`Equipment`] changed,
+/// or whose scene just spawned
+pub fn update_equipment(
+    mut commands: Commands,
+    mut assets: EquipmentAssets,
+    registry: Res<ModelManifests>,
+    manifests: Res<Assets<ModelManifest>>,
+    models: EquipmentChanges,
+    mut pieces: Query<&mut EquipmentPieces>,
+    parts: ModelParts,
+) {
+    for (entity, equipment, model) in &models {
+        let mut spawned = Vec::new();
+        let manifest = registry.get(&manifests, &model.entity_type);
+
+        for slot in EquipmentSlot::ARMOR {
+            let (Some(manifest), Some(path)) = (
+                manifest,
+                equipment
+                    .get(slot)
+                    .and_then(|item| armor_texture_path(item, slot)),
+            ) else {
+                continue;
+            };
+            let Some(slot_bones) = manifest.armor.get(&slot) else {
+                continue;
+            };
+            let material = assets.armor_material(path);
+
+            // Bones of other slots keep their own meshes, and earlier
+            // pieces are on their way out
+            let listed: Vec<Entity> = manifest
+                .armor
+                .values()
+                .flatten()
+                .filter_map(|bone| parts.bones.find(entity, bone))
+                .collect();
+            let skip = |part: Entity| {
+                listed.contains(&part) || parts.pieces.contains(part)
+            };
+            for bone in slot_bones
+                .iter()
+                .filter_map(|b| parts.bones.find(entity, b))
+            {
+                for part in bone_parts(bone, &skip, &parts.children) {
+                    let Ok(Mesh3d(handle)) = parts.meshes.get(part) else {
+                        continue;
+                    };
+                    let Some(mesh) = assets.meshes.get(handle) else {
+                        continue;
+                    };
+                    let piece = commands
+                        .spawn((
+                            Mesh3d(handle.clone()),
+                            MeshMaterial3d(material.clone()),
+                            inflated(mesh, slot.inflate()),
+                            EquipmentPiece,
+                            ChildOf(part),
+                        ))
+                        .id();
+                    spawned.push(piece);
+                }
+            }
+        }
+
+        for slot in [EquipmentSlot::MainHand, EquipmentSlot::OffHand] {
+            let (Some(item), Some(joint)) =
+                (equipment.get(slot), slot.hand_joint())
+            else {
+                continue;
+            };
+            let holder = commands
+                .spawn((Attachment::new(entity, joint), EquipmentPiece))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Transform::from_xyz(0.0, 0.2, 0.0)
+                            .with_rotation(Quat::from_rotation_y(FRAC_PI_2))
+                            .with_scale(Vec3::splat(HELD_ITEM_SCALE)),
+                        ItemModel::new(item, &assets.asset_server),
+                    ));
+                })
+                .id();
+            spawned.push(holder);
+        }
+
+        if let Ok(mut old) = pieces.get_mut(entity) {
+            old.despawn(&mut commands);
+            old.0 = spawned;
+        } else {
+            commands.entity(entity).insert(EquipmentPieces(spawned));
+        }
+    }
+}
+
+/// Take the pieces off models whose [`Equipment`] was removed
+pub fn remove_equipment(
+    mut commands: Commands,
+    mut removed: RemovedComponents<Equipment>,
+    mut pieces: Query<&mut EquipmentPieces>,
+) {
+    for entity in removed.read() {
+        // Despawned along with the model otherwise
+        let Ok(mut old) = pieces.get_mut(entity) else {
+            continue;
+        };
+        old.despawn(&mut commands);
+        commands.entity(entity).remove::<EquipmentPieces>();
+    }
+}
+
+/// `bone` and everything below it, short of the `skip`ped entities
+fn bone_parts(
+    bone: Entity,
+    skip: &impl Fn(Entity) -> bool,
+    children: &Query<&Children>,
+) -> Vec<Entity> {
+    let mut parts = Vec::new();
+    let mut stack = vec![bone];
+    while let Some(entity) = stack.pop() {
+        parts.push(entity);
+        if let Ok(entity_children) = children.get(entity) {
+            stack.extend(
+                entity_children
+                    .iter()
+                    .copied()
+                    .filter(|&child| !skip(child)),
+            );
+        }
+    }
+    parts
+}
+
+/// Grow a mesh by `pixels` on every side, around its center
+fn inflated(mesh: &Mesh, pixels: f32) -> Transform {
+    let Some(aabb) = mesh.compute_aabb() else {
+        return Transform::IDENTITY;
+    };
+    let center = Vec3::from(aabb.center);
+    let half = Vec3::from(aabb.half_extents);
+    let grow = pixels / 16.0;
+    let scale =
+        Vec3::select(half.cmpgt(Vec3::ZERO), (half + grow) / half, Vec3::ONE);
+    Transform::from_translation(center - center * scale).with_scale(scale)
+}
@@ -0,0 +1,92 @@
+use bevy::{
+    asset::{AssetServer, Assets, Handle},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        hierarchy::{ChildOf, Children},
+        query::{Changed, With},
+        resource::Resource,
+        system::{Commands, Query, Res, ResMut},
+    },
+    math::{primitives::Cuboid, Vec3},
+    pbr::{MeshMaterial3d, StandardMaterial},
+    render::mesh::{Mesh, Mesh3d},
+    transform::components::Transform,
+};
+
+use crate::item::{mesh::ItemModel, Item};
+
+/// Side of the frame's board, in blocks
+const FRAME_SIZE: f32 = 12.0 / 16.0;
+const FRAME_DEPTH: f32 = 1.0 / 16.0;
+/// Size of the item shown on the board, in blocks
+const ITEM_SCALE: f32 = 0.5;
+
+/// Board hung on a wall showing an item. The transform is the center of
+/// the board's back, facing +Z.
+#[derive(Component, Debug, Clone, Default)]
+pub struct ItemFrame {
+    pub item: Option<Item>,
+}
+
+/// Board and item spawned for an [`ItemFrame`]
+#[derive(Component)]
+pub struct ItemFramePart;
+
+#[derive(Resource)]
+pub struct ItemFrameAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+pub fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(ItemFrameAssets {
+        mesh: meshes.add(Cuboid::new(FRAME_SIZE, FRAME_SIZE, FRAME_DEPTH)),
+        material: materials.add(StandardMaterial {
+            base_color_texture: Some(
+                asset_server.load("images/blocks/planks_oak.png"),
+            ),
+            perceptual_roughness: 1.0,
+            ..StandardMaterial::default()
+        }),
+    });
+}
+
+/// Rebuild the board and item of frames that were added or changed
+pub fn update_item_frames(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    frame_assets: Res<ItemFrameAssets>,
+    frames: Query<(Entity, &ItemFrame, Option<&Children>), Changed<ItemFrame>>,
+    parts: Query<(), With<ItemFramePart>>,
+) {
+    for (entity, frame, children) in &frames {
+        for &child in children.into_iter().flatten() {
+            if parts.contains(child) {
+                commands.entity(child).despawn();
+            }
+        }
+
+        commands.spawn((
+            Mesh3d(frame_assets.mesh.clone()),
+            MeshMaterial3d(frame_assets.material.clone()),
+            Transform::from_xyz(0.0, 0.0, FRAME_DEPTH / 2.0),
+            ItemFramePart,
+            ChildOf(entity),
+        ));
+        if let Some(item) = frame.item {
+            commands.spawn((
+                ItemModel::new(item, &asset_server),
+                Transform::from_xyz(0.0, 0.0, FRAME_DEPTH * 1.5)
+                    .with_scale(Vec3::splat(ITEM_SCALE)),
+                ItemFramePart,
+                ChildOf(entity),
+            ));
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use bevy::{
+    asset::Assets,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::EventWriter,
+        query::{Changed, Has, With, Without},
+        removal_detection::RemovedComponents,
+        system::{Commands, Query, Res},
+    },
+    input::{keyboard::KeyCode, ButtonInput},
+    math::{Quat, Vec3},
+    time::Time,
+    transform::components::{GlobalTransform, Transform},
+};
+
+use crate::{
+    animation::{ModelAnimation, PlayAnimation},
+    model::{
+        animation::DEFAULT_FADE,
+        assembly::{AttachEntity, Attachment, DetachEntity, Joints},
+        manifest::{Model, ModelManifest, ModelManifests},
+    },
+    simple_control::{Player, PLAYER_EYE_HEIGHT},
+};
+
+/// Joint of a mount its riders sit on
+pub const SEAT_JOINT: &str = "seat";
+/// Joint of a rider put on the seat
+pub const HIPS_JOINT: &str = "hips";
+
+/// Height of the player's eyes above a seat, in blocks
+const SEATED_EYE_HEIGHT: f32 = 0.9;
+/// Blocks per second of a mount driven by the player
+const MOUNT_SPEED: f32 = 4.0;
+/// How far a free seat can be for the player to get on, in blocks
+const MOUNT_RANGE: f32 = 3.0;
+/// Longest chain of mounts followed, guards against riding in a circle
+const MAX_STACK: usize = 16;
+
+/// Sits an entity on the seat of another. A mount can itself ride, which
+/// stacks riders.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+#[relationship(relationship_target = Riders)]
+pub struct Rides(pub Entity);
+
+/// Entities riding a mount, kept by [`Rides`]
+#[derive(Component, Debug, Default)]
+#[relationship_target(relationship = Rides)]
+pub struct Riders(Vec<Entity>);
+
+/// Animation a rider played before sitting down, restored when it gets off
+#[derive(Component, Debug, Clone)]
+pub struct RidingPose {
+    pub previous: Option<String>,
+}
+
+/// Rider whose manifest wasn't loaded yet when it sat down, posed by
+/// [`pose_riders`] once it is
+#[derive(Component, Debug)]
+pub struct PendingRidingPose;
+
+type NewRider<'a> = (Entity, &'a Rides, Has<RidingPose>, Has<Player>);
+
+/// Put new and changed riders on their mount's seat
+pub fn mount_riders(
+    mut commands: Commands,
+    mut attach_events: EventWriter<AttachEntity>,
+    riders: Query<NewRider, Changed<Rides>>,
+) {
+    for (entity, rides, posed, is_player) in &riders {
+        let mut attachment =
+            Attachment::new(rides.0, SEAT_JOINT).with_carried_joint(HIPS_JOINT);
+        if is_player {
+            attachment = attachment.with_offset(Vec3::Y * SEATED_EYE_HEIGHT);
+        }
+        attach_events.write(AttachEntity { entity, attachment });
+
+        // Moving to another mount keeps the pose from the first
+        if !posed {
+            commands.entity(entity).insert(PendingRidingPose);
+        }
+    }
+}
+
+type UnposedRiders<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        Option<&'static Model>,
+        Option<&'static ModelAnimation>,
+    ),
+    (With<Rides>, With<PendingRidingPose>),
+>;
+
+/// Switch riders to their manifest's riding animation. Riders stay pending
+/// until their manifest is loaded, or are dropped if they have no model.
+pub fn pose_riders(
+    mut commands: Commands,
+    registry: Res<ModelManifests>,
+    manifests: Res<Assets<ModelManifest>>,
+    mut play_events: EventWriter<PlayAnimation>,
+    riders: UnposedRiders,
+) {
+    for (entity, model, animation) in &riders {
+        let manifest = match model {
+            Some(model) => match registry.get(&manifests, &model.entity_type) {
+                Some(manifest) => Some(manifest),
+                None => continue,
+            },
+            None => None,
+        };
+        commands.entity(entity).remove::<PendingRidingPose>();
+        let Some(riding) =
+            manifest.and_then(|manifest| manifest.riding_animation.clone())
+        else {
+            continue;
+        };
+
+        commands.entity(entity).insert(RidingPose {
+            previous: animation.map(|animation| animation.0.clone()),
+        });
+        if animation.is_none() {
+            commands.entity(entity).insert(ModelAnimation::new(&riding));
+        }
+        play_events.write(PlayAnimation {
+            entity,
+            name: riding,
+            fade: DEFAULT_FADE,
+        });
+    }
+}
+
+/// Take riders whose [`Rides`] was removed off their mount, back in the
+/// animation they had before or their manifest's default. Riders with
+/// neither lose their [`ModelAnimation`], which stops their players.
+pub fn dismount_riders(
+    mut commands: Commands,
+    registry: Res<ModelManifests>,
+    manifests: Res<Assets<ModelManifest>>,
+    mut removed: RemovedComponents<Rides>,
+    mut detach_events: EventWriter<DetachEntity>,
+    mut play_events: EventWriter<PlayAnimation>,
+    riders: Query<(Option<&Model>, Option<&RidingPose>), Without<Rides>>,
+) {
+    for entity in removed.read() {
+        // Despawned, or already riding again
+        let Ok((model, pose)) = riders.get(entity) else {
+            continue;
+        };
+        detach_events.write(DetachEntity { entity });
+        commands.entity(entity).remove::<PendingRidingPose>();
+
+        let Some(pose) = pose else {
+            continue;
+        };
+        commands.entity(entity).remove::<RidingPose>();
+        let previous = pose.previous.clone().or_else(|| {
+            let manifest = registry.get(&manifests, &model?.entity_type)?;
+            manifest.default_animation.clone()
+        });
+        match previous {
+            Some(name) => {
+                play_events.write(PlayAnimation {
+                    entity,
+                    name,
+                    fade: DEFAULT_FADE,
+                });
+            }
+            // Riding was its first animation, go back to none
+            None => {
+                commands.entity(entity).remove::<ModelAnimation>();
+            }
+        }
+    }
+}
+
+/// Get the player on the nearest free seat with R
+pub fn mount_nearest(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut commands: Commands,
+    players: Query<(Entity, &Transform, Has<Rides>), With<Player>>,
+    mounts: Query<(Entity, &GlobalTransform, &Joints), Without<Riders>>,
+) {
+    if !keys.just_pressed(KeyCode::KeyR) {
+        return;
+    }
+    let Ok((player, transform, false)) = players.single() else {
+        return;
+    };
+    let feet = transform.translation - Vec3::Y * PLAYER_EYE_HEIGHT;
+
+    let nearest = mounts
+        .iter()
+        .filter(|(_, _, joints)| joints.get(SEAT_JOINT).is_some())
+        .map(|(entity, mount, _)| (entity, mount.translation().distance(feet)))
+        .filter(|&(_, distance)| distance <= MOUNT_RANGE)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b));
+    if let Some((mount, _)) = nearest {
+        commands.entity(player).insert(Rides(mount));
+    }
+}
+
+/// Steer the bottom mount under a riding player with the movement keys,
+/// facing where the player looks. Shift gets off.
+pub fn drive_mounts(
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut commands: Commands,
+    players: Query<(Entity, &Player, &Rides)>,
+    rides: Query<&Rides>,
+    mut mounts: Query<&mut Transform, (Without<Player>, With<Riders>)>,
+) {
+    for (entity, player, player_rides) in &players {
+        if keys.pressed(KeyCode::ShiftLeft) || keys.pressed(KeyCode::ShiftRight)
+        {
+            commands.entity(entity).remove::<Rides>();
+            continue;
+        }
+
+        let mut mount = player_rides.0;
+        for _ in 0..MAX_STACK {
+            let Ok(next) = rides.get(mount) else {
+                break;
+            };
+            mount = next.0;
+        }
+        let Ok(mut transform) = mounts.get_mut(mount) else {
+            continue;
+        };
+
+        let rotation = Quat::from_rotation_y(player.yaw);
+        let forward = rotation * Vec3::NEG_Z;
+        let right = rotation * Vec3::X;
+        let mut velocity = Vec3::ZERO;
+        if keys.pressed(KeyCode::KeyW) {
+            velocity += forward;
+        }
+        if keys.pressed(KeyCode::KeyS) {
+            velocity -= forward;
+        }
+        if keys.pressed(KeyCode::KeyA) {
+            velocity -= right;
+        }
+        if keys.pressed(KeyCode::KeyD) {
+            velocity += right;
+        }
+
+        transform.rotation = rotation;
+        transform.translation +=
+            velocity.normalize_or_zero() * MOUNT_SPEED * time.delta_secs();
+    }
+}
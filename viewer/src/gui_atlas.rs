@@ -5,31 +5,28 @@ use bevy::{
 };
 use bevy_image::{Image, TextureAtlasLayout};
 
-use crate::{atlas_enum::AtlasEnum, region::Region};
-
-#[derive(strum_macros::EnumIter, Copy, Clone)]
-pub enum IconsAtlas {
-    Crosshair,
-}
-
-impl AtlasEnum for IconsAtlas {
-    fn get_region(&self) -> Region {
-        match self {
-            IconsAtlas::Crosshair => Region {
-                x: 0,
-                y: 0,
-                w: 16,
-                h: 16,
-            },
-        }
-    }
-
-    fn texture_source() -> (&'static str, UVec2) {
-        ("images/gui/icons.png", UVec2::new(256, 256))
-    }
-
-    fn get_index(&self) -> usize {
-        *self as usize
+use crate::{atlas_enum, region::Region};
+
+atlas_enum! {
+    /// Regions of the standard `icons.png` HUD sheet. Hearts, armor and
+    /// hunger icons are 9×9, the experience bar is 182×5.
+    pub enum IconsAtlas from "atlases/icons.atlas.ron" {
+        Crosshair = "crosshair",
+        HeartContainer = "heart_container",
+        HeartContainerBlink = "heart_container_blink",
+        HeartFull = "heart_full",
+        HeartHalf = "heart_half",
+        HeartFullBlink = "heart_full_blink",
+        HeartHalfBlink = "heart_half_blink",
+        ArmorEmpty = "armor_empty",
+        ArmorHalf = "armor_half",
+        ArmorFull = "armor_full",
+        HungerContainer = "hunger_container",
+        HungerContainerBlink = "hunger_container_blink",
+        HungerFull = "hunger_full",
+        HungerHalf = "hunger_half",
+        ExperienceBarEmpty = "experience_bar_empty",
+        ExperienceBarFull = "experience_bar_full",
     }
 }
 
@@ -0,0 +1,375 @@
+use bevy::{
+    asset::Handle,
+    color::Color,
+    ecs::{
+        component::Component,
+        hierarchy::ChildSpawnerCommands,
+        query::{With, Without},
+        system::{Commands, Local, Query, Res},
+    },
+    render::view::Visibility,
+    text::{TextColor, TextFont},
+    time::Time,
+    ui::{
+        widget::{ImageNode, Text},
+        AlignItems, FlexDirection, JustifyContent, Node, Overflow,
+        PositionType, UiRect, Val,
+    },
+    utils::default,
+};
+use bevy_image::{Image, TextureAtlasLayout};
+
+use crate::{
+    atlas_enum::AtlasEnum,
+    components::player_stats::{Armor, Experience, Health, Hunger},
+    gui_atlas::IconsAtlas,
+    simple_control::Player,
+};
+
+/// Hearts, drumsticks and armor icons per row
+const ICONS_PER_ROW: usize = 10;
+/// On-screen size of one 9×9 icon
+const ICON_SIZE: f32 = 18.0;
+/// Matches the hotbar: 9 slots of 44px
+const HUD_WIDTH: f32 = 396.0;
+const EXPERIENCE_BAR_HEIGHT: f32 = 10.0;
+/// Room left at the bottom for the hotbar
+const HOTBAR_OFFSET: f32 = 56.0;
+
+/// How long icons blink after the stat changes
+const BLINK_DURATION: f32 = 1.0;
+const BLINK_INTERVAL: f32 = 0.15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stat {
+    Health,
+    Hunger,
+    Armor,
+}
+
+/// Background icon of one stat slot; the fill is a child [`StatIconFill`]
+#[derive(Component)]
+pub struct StatIcon {
+    pub stat: Stat,
+    pub index: usize,
+}
+
+#[derive(Component)]
+pub struct StatIconFill {
+    pub stat: Stat,
+    pub index: usize,
+}
+
+#[derive(Component)]
+pub struct ExperienceBarFill;
+
+#[derive(Component)]
+pub struct ExperienceLevelText;
+
+/// Tracks the last shown values so icons can blink when they change
+#[derive(Default)]
+pub struct HudBlink {
+    health: Option<u32>,
+    hunger: Option<u32>,
+    /// Value before the last drop, shown in the blink color
+    health_before: u32,
+    health_timer: f32,
+    hunger_timer: f32,
+}
+
+pub fn setup(
+    commands: &mut Commands,
+    texture_handle: Handle<Image>,
+    atlas_layout_handle: Handle<TextureAtlasLayout>,
+) {
+    let image = |icon: IconsAtlas| {
+        icon.to_image_node(texture_handle.clone(), atlas_layout_handle.clone())
+    };
+
+    commands
+        .spawn(Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::FlexEnd,
+            align_items: AlignItems::Center,
+            padding: UiRect::bottom(Val::Px(HOTBAR_OFFSET)),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    width: Val::Px(HUD_WIDTH),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(2.0),
+                    ..default()
+                })
+                .with_children(|hud| {
+                    spawn_icon_row(
+                        hud,
+                        &image,
+                        Stat::Armor,
+                        FlexDirection::Row,
+                    );
+
+                    hud.spawn(Node {
+                        justify_content: JustifyContent::SpaceBetween,
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        spawn_icon_row(
+                            row,
+                            &image,
+                            Stat::Health,
+                            FlexDirection::Row,
+                        );
+                        // Hunger fills from the right, like Minecraft
+                        spawn_icon_row(
+                            row,
+                            &image,
+                            Stat::Hunger,
+                            FlexDirection::RowReverse,
+                        );
+                    });
+
+                    hud.spawn((
+                        Node {
+                            width: Val::Px(HUD_WIDTH),
+                            height: Val::Px(EXPERIENCE_BAR_HEIGHT),
+                            justify_content: JustifyContent::Center,
+                            ..default()
+                        },
+                        image(IconsAtlas::ExperienceBarEmpty),
+                    ))
+                    .with_children(|bar| {
+                        // The fill is clipped by its parent's width
+                        bar.spawn((
+                            Node {
+                                position_type: PositionType::Absolute,
+                                left: Val::Px(0.0),
+                                width: Val::Percent(0.0),
+                                height: Val::Percent(100.0),
+                                overflow: Overflow::clip(),
+                                ..default()
+                            },
+                            ExperienceBarFill,
+                        ))
+                        .with_children(|fill| {
+                            fill.spawn((
+                                Node {
+                                    width: Val::Px(HUD_WIDTH),
+                                    min_width: Val::Px(HUD_WIDTH),
+                                    height: Val::Px(EXPERIENCE_BAR_HEIGHT),
+                                    ..default()
+                                },
+                                image(IconsAtlas::ExperienceBarFull),
+                            ));
+                        });
+
+                        bar.spawn((
+                            Node {
+                                position_type: PositionType::Absolute,
+                                bottom: Val::Px(2.0),
+                                ..default()
+                            },
+                            Text::new(""),
+                            TextFont {
+                                font_size: 16.0,
+                                ..default()
+                            },
+                            TextColor(Color::srgb(0.5, 1.0, 0.125)),
+                            ExperienceLevelText,
+                        ));
+                    });
+                });
+        });
+}
+
+fn spawn_icon_row(
+    parent: &mut ChildSpawnerCommands,
+    image: &impl Fn(IconsAtlas) -> ImageNode,
+    stat: Stat,
+    flex_direction: FlexDirection,
+) {
+    parent
+        .spawn(Node {
+            flex_direction,
+            ..default()
+        })
+        .with_children(|row| {
+            for index in 0..ICONS_PER_ROW {
+                // Icons are set by `update_stat_icons`, start out empty
+                let (background, fill) = stat_icons(stat, index, 0, None);
+
+                row.spawn((
+                    Node {
+                        width: Val::Px(ICON_SIZE),
+                        height: Val::Px(ICON_SIZE),
+                        ..default()
+                    },
+                    image(background),
+                    StatIcon { stat, index },
+                ))
+                .with_children(|icon| {
+                    icon.spawn((
+                        Node {
+                            position_type: PositionType::Absolute,
+                            width: Val::Percent(100.0),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        image(fill.unwrap_or(background)),
+                        Visibility::Hidden,
+                        StatIconFill { stat, index },
+                    ));
+                });
+            }
+        });
+}
+
+/// Background and fill for icon `index` of a stat holding `value` halves.
+/// `blinking_value` is the value drawn with blink icons, if any.
+fn stat_icons(
+    stat: Stat,
+    index: usize,
+    value: u32,
+    blinking_value: Option<u32>,
+) -> (IconsAtlas, Option<IconsAtlas>) {
+    let halves_at = |value: u32| value.saturating_sub(index as u32 * 2).min(2);
+
+    match stat {
+        Stat::Health => {
+            let background = if blinking_value.is_some() {
+                IconsAtlas::HeartContainerBlink
+            } else {
+                IconsAtlas::HeartContainer
+            };
+            let fill = match (halves_at(value), blinking_value.map(halves_at)) {
+                (2, _) => Some(IconsAtlas::HeartFull),
+                (1, Some(2)) => Some(IconsAtlas::HeartFullBlink),
+                (1, _) => Some(IconsAtlas::HeartHalf),
+                (_, Some(2)) => Some(IconsAtlas::HeartFullBlink),
+                (_, Some(1)) => Some(IconsAtlas::HeartHalfBlink),
+                _ => None,
+            };
+            (background, fill)
+        }
+        Stat::Hunger => {
+            let background = if blinking_value.is_some() {
+                IconsAtlas::HungerContainerBlink
+            } else {
+                IconsAtlas::HungerContainer
+            };
+            let fill = match halves_at(value) {
+                2 => Some(IconsAtlas::HungerFull),
+                1 => Some(IconsAtlas::HungerHalf),
+                _ => None,
+            };
+            (background, fill)
+        }
+        Stat::Armor => {
+            let fill = match halves_at(value) {
+                2 => Some(IconsAtlas::ArmorFull),
+                1 => Some(IconsAtlas::ArmorHalf),
+                _ => None,
+            };
+            (IconsAtlas::ArmorEmpty, fill)
+        }
+    }
+}
+
+pub fn update_stat_icons(
+    time: Res<Time>,
+    mut blink: Local<HudBlink>,
+    player_query: Query<(&Health, &Hunger, &Armor), With<Player>>,
+    mut icons: Query<(&StatIcon, &mut ImageNode, &mut Visibility)>,
+    mut fills: Query<
+        (&StatIconFill, &mut ImageNode, &mut Visibility),
+        Without<StatIcon>,
+    >,
+) {
+    let Ok((health, hunger, armor)) = player_query.single() else {
+        return;
+    };
+
+    let dt = time.delta_secs();
+    blink.health_timer = (blink.health_timer - dt).max(0.0);
+    blink.hunger_timer = (blink.hunger_timer - dt).max(0.0);
+
+    if let Some(last) = blink.health.filter(|&last| last != health.current) {
+        blink.health_before = last.max(health.current);
+        blink.health_timer = BLINK_DURATION;
+    }
+    if blink.hunger.is_some_and(|last| last != hunger.current) {
+        blink.hunger_timer = BLINK_DURATION;
+    }
+    blink.health = Some(health.current);
+    blink.hunger = Some(hunger.current);
+
+    // Alternate between normal and blink icons while the timer runs
+    let blink_on = |timer: f32| {
+        timer > 0.0 && ((timer / BLINK_INTERVAL) as u32).is_multiple_of(2)
+    };
+    let health_blink =
+        blink_on(blink.health_timer).then_some(blink.health_before);
+    let hunger_blink = blink_on(blink.hunger_timer).then_some(hunger.current);
+
+    let resolve = |stat: Stat, index: usize| {
+        let (value, max, blinking) = match stat {
+            Stat::Health => (health.current, health.max, health_blink),
+            Stat::Hunger => (hunger.current, hunger.max, hunger_blink),
+            // The armor row only shows up once there is some armor
+            Stat::Armor if armor.0 == 0 => (0, 0, None),
+            Stat::Armor => (armor.0, ICONS_PER_ROW as u32 * 2, None),
+        };
+        let shown = index < max.div_ceil(2) as usize;
+        (shown, stat_icons(stat, index, value, blinking))
+    };
+
+    for (icon, mut image, mut visibility) in &mut icons {
+        let (shown, (background, _)) = resolve(icon.stat, icon.index);
+        *visibility = if shown {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if let Some(atlas) = image.texture_atlas.as_mut() {
+            atlas.index = background.get_index();
+        }
+    }
+
+    for (icon, mut image, mut visibility) in &mut fills {
+        let (_, (_, fill)) = resolve(icon.stat, icon.index);
+        match fill {
+            Some(fill) => {
+                *visibility = Visibility::Inherited;
+                if let Some(atlas) = image.texture_atlas.as_mut() {
+                    atlas.index = fill.get_index();
+                }
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+pub fn update_experience_bar(
+    player_query: Query<&Experience, With<Player>>,
+    mut experience_fill: Query<&mut Node, With<ExperienceBarFill>>,
+    mut experience_text: Query<&mut Text, With<ExperienceLevelText>>,
+) {
+    let Ok(experience) = player_query.single() else {
+        return;
+    };
+
+    if let Ok(mut node) = experience_fill.single_mut() {
+        node.width = Val::Percent(experience.progress.clamp(0.0, 1.0) * 100.0);
+    }
+    if let Ok(mut text) = experience_text.single_mut() {
+        text.0 = if experience.level > 0 {
+            experience.level.to_string()
+        } else {
+            String::new()
+        };
+    }
+}
@@ -0,0 +1,301 @@
+use bevy::{
+    asset::AssetServer,
+    color::Color,
+    ecs::{
+        change_detection::DetectChanges,
+        component::Component,
+        event::EventReader,
+        resource::Resource,
+        system::{Commands, Query, Res, ResMut},
+    },
+    input::{keyboard::KeyCode, mouse::AccumulatedMouseScroll, ButtonInput},
+    log::debug,
+    text::{TextColor, TextFont},
+    ui::{
+        widget::{ImageNode, Text},
+        AlignItems, BackgroundColor, BorderColor, FlexDirection,
+        JustifyContent, Node, PositionType, UiRect, Val,
+    },
+    utils::default,
+};
+
+use crate::{
+    block::BuiltBlockID,
+    entity::dropping_item::ItemPickedUp,
+    item::{Item, MAX_STACK_SIZE},
+};
+
+pub const HOTBAR_SIZE: usize = 9;
+/// Hotbar slots come first, followed by the main inventory
+pub const INVENTORY_SIZE: usize = 36;
+
+const SLOT_SIZE: f32 = 44.0;
+const ICON_SIZE: f32 = 32.0;
+const SLOT_BORDER: Color = Color::srgb(0.35, 0.35, 0.35);
+
+const HOTBAR_KEYS: [KeyCode; HOTBAR_SIZE] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ItemStack {
+    pub item: Item,
+    pub count: u32,
+}
+
+#[derive(Resource)]
+pub struct Inventory {
+    pub slots: [Option<ItemStack>; INVENTORY_SIZE],
+    /// Index of the selected hotbar slot
+    pub selected: usize,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self {
+            slots: [None; INVENTORY_SIZE],
+            selected: 0,
+        }
+    }
+}
+
+impl Inventory {
+    /// How many of `item` still fit, across partial stacks and empty slots
+    pub fn room_for(&self, item: Item) -> u32 {
+        self.slots
+            .iter()
+            .map(|slot| match slot {
+                Some(stack) if stack.item == item => {
+                    MAX_STACK_SIZE - stack.count
+                }
+                Some(_) => 0,
+                None => MAX_STACK_SIZE,
+            })
+            .sum()
+    }
+
+    /// Add items, topping up existing stacks before filling empty slots.
+    /// Returns the count that didn't fit.
+    pub fn add(&mut self, item: Item, mut count: u32) -> u32 {
+        for stack in self.slots.iter_mut().flatten() {
+            if count == 0 {
+                return 0;
+            }
+            if stack.item == item && stack.count < MAX_STACK_SIZE {
+                let moved = count.min(MAX_STACK_SIZE - stack.count);
+                stack.count += moved;
+                count -= moved;
+            }
+        }
+
+        for slot in self.slots.iter_mut().filter(|slot| slot.is_none()) {
+            if count == 0 {
+                return 0;
+            }
+            let moved = count.min(MAX_STACK_SIZE);
+            *slot = Some(ItemStack { item, count: moved });
+            count -= moved;
+        }
+
+        count
+    }
+
+    pub fn selected_stack(&self) -> Option<ItemStack> {
+        self.slots[self.selected]
+    }
+
+    /// The block placed by the selected item, if it is a block
+    pub fn selected_block(&self) -> Option<BuiltBlockID> {
+        self.selected_stack()?.item.block()
+    }
+
+    /// Remove one item from the selected slot
+    pub fn take_selected(&mut self) -> Option<Item> {
+        let slot = &mut self.slots[self.selected];
+        let stack = slot.as_mut()?;
+        let item = stack.item;
+
+        stack.count -= 1;
+        if stack.count == 0 {
+            *slot = None;
+        }
+        Some(item)
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.selected = index % HOTBAR_SIZE;
+    }
+
+    /// Move the selection by `steps`, wrapping around the hotbar
+    pub fn scroll(&mut self, steps: i32) {
+        let selected = self.selected as i32 + steps;
+        self.selected = selected.rem_euclid(HOTBAR_SIZE as i32) as usize;
+    }
+}
+
+#[derive(Component)]
+pub struct HotbarSlot(pub usize);
+
+#[derive(Component)]
+pub struct HotbarIcon(pub usize);
+
+#[derive(Component)]
+pub struct HotbarCount(pub usize);
+
+/// Insert an [`Inventory`] holding one stack of every block and spawn the
+/// hotbar along the bottom of the screen
+pub fn setup(mut commands: Commands) {
+    let mut inventory = Inventory::default();
+    for block in [
+        BuiltBlockID::Brick,
+        BuiltBlockID::Dirt,
+        BuiltBlockID::Grass,
+        BuiltBlockID::PlanksOak,
+        BuiltBlockID::WoolColoredOrange,
+    ] {
+        inventory.add(Item::Block(block), MAX_STACK_SIZE);
+    }
+    commands.insert_resource(inventory);
+
+    commands
+        .spawn(Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::FlexEnd,
+            padding: UiRect::bottom(Val::Px(8.0)),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    ..default()
+                })
+                .with_children(|hotbar| {
+                    for index in 0..HOTBAR_SIZE {
+                        hotbar
+                            .spawn((
+                                Node {
+                                    width: Val::Px(SLOT_SIZE),
+                                    height: Val::Px(SLOT_SIZE),
+                                    border: UiRect::all(Val::Px(2.0)),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                BackgroundColor(Color::srgba(
+                                    0.0, 0.0, 0.0, 0.5,
+                                )),
+                                BorderColor(SLOT_BORDER),
+                                HotbarSlot(index),
+                            ))
+                            .with_children(|slot| {
+                                slot.spawn((
+                                    Node {
+                                        width: Val::Px(ICON_SIZE),
+                                        height: Val::Px(ICON_SIZE),
+                                        ..default()
+                                    },
+                                    ImageNode::default(),
+                                    HotbarIcon(index),
+                                ));
+                                slot.spawn((
+                                    Node {
+                                        position_type: PositionType::Absolute,
+                                        right: Val::Px(2.0),
+                                        bottom: Val::Px(0.0),
+                                        ..default()
+                                    },
+                                    Text::new(""),
+                                    TextFont {
+                                        font_size: 14.0,
+                                        ..default()
+                                    },
+                                    TextColor(Color::WHITE),
+                                    HotbarCount(index),
+                                ));
+                            });
+                    }
+                });
+        });
+}
+
+/// Number keys pick a slot directly, the mouse wheel cycles through them
+pub fn select_hotbar_slot(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_scroll: Res<AccumulatedMouseScroll>,
+    mut inventory: ResMut<Inventory>,
+) {
+    if let Some(index) = HOTBAR_KEYS
+        .iter()
+        .position(|&key| keyboard_input.just_pressed(key))
+    {
+        inventory.select(index);
+        return;
+    }
+
+    let scroll = mouse_scroll.delta.y;
+    if scroll != 0.0 {
+        // Scrolling down moves to the right, like Minecraft
+        inventory.scroll(-scroll.signum() as i32);
+    }
+}
+
+pub fn collect_picked_up_items(
+    mut picked_up_events: EventReader<ItemPickedUp>,
+    mut inventory: ResMut<Inventory>,
+) {
+    for event in picked_up_events.read() {
+        let left = inventory.add(event.item, event.count);
+        if left > 0 {
+            debug!("Inventory full, lost {} of {:?}", left, event.item);
+        }
+    }
+}
+
+pub fn update_hotbar(
+    inventory: Res<Inventory>,
+    asset_server: Res<AssetServer>,
+    mut slots: Query<(&HotbarSlot, &mut BorderColor)>,
+    mut icons: Query<(&HotbarIcon, &mut ImageNode)>,
+    mut counts: Query<(&HotbarCount, &mut Text)>,
+) {
+    if !inventory.is_changed() {
+        return;
+    }
+
+    for (slot, mut border) in &mut slots {
+        border.0 = if slot.0 == inventory.selected {
+            Color::WHITE
+        } else {
+            SLOT_BORDER
+        };
+    }
+
+    for (icon, mut image) in &mut icons {
+        match inventory.slots[icon.0] {
+            Some(stack) => {
+                image.image = asset_server.load(stack.item.texture_path());
+                image.color = Color::WHITE;
+            }
+            None => image.color = Color::NONE,
+        }
+    }
+
+    for (count, mut text) in &mut counts {
+        text.0 = match inventory.slots[count.0] {
+            Some(stack) if stack.count > 1 => stack.count.to_string(),
+            _ => String::new(),
+        };
+    }
+}
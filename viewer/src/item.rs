@@ -0,0 +1,48 @@
+pub mod mesh;
+
+use crate::block::BuiltBlockID;
+
+pub type ItemID = &'static str;
+
+/// Largest number of one item kept in a single stack
+pub const MAX_STACK_SIZE: u32 = 64;
+
+/// Anything that can be dropped, held or stored: either a placeable block
+/// or a plain item drawn from `images/items`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Item {
+    Block(BuiltBlockID),
+    Named(ItemID),
+}
+
+impl Item {
+    pub fn texture_path(&self) -> String {
+        match self {
+            Item::Block(block) => format!(
+                "images/blocks/{}",
+                block.texture().unwrap_or("missing_tile.png")
+            ),
+            Item::Named(item) => format!("images/items/{}.png", item),
+        }
+    }
+
+    /// The block placed when this item is used, if any
+    pub fn block(&self) -> Option<BuiltBlockID> {
+        match *self {
+            Item::Block(block) if block != BuiltBlockID::Air => Some(block),
+            _ => None,
+        }
+    }
+}
+
+impl From<BuiltBlockID> for Item {
+    fn from(block: BuiltBlockID) -> Self {
+        Item::Block(block)
+    }
+}
+
+impl From<ItemID> for Item {
+    fn from(item: ItemID) -> Self {
+        Item::Named(item)
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+
+use bevy::{
+    asset::{AssetId, AssetServer, Assets, Handle, RenderAssetUsages},
+    color::Alpha,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{Changed, Or, Without},
+        resource::Resource,
+        system::{Commands, Query, Res, ResMut},
+    },
+    math::Vec3,
+    pbr::{MeshMaterial3d, StandardMaterial},
+    render::{
+        alpha::AlphaMode,
+        mesh::{Indices, Mesh, Mesh3d, PrimitiveTopology},
+    },
+};
+use bevy_image::Image;
+
+use super::Item;
+
+/// Pixels less opaque than this are holes in the sprite
+pub const ALPHA_CUTOFF: f32 = 0.05;
+
+/// An item's sprite extruded into a 3D model one block wide and one pixel
+/// deep, centered on the origin and facing +Z. The mesh is built once the
+/// texture has loaded.
+#[derive(Component, Debug, Clone)]
+pub struct ItemModel {
+    pub item: Item,
+    pub texture: Handle<Image>,
+}
+
+impl ItemModel {
+    pub fn new(item: Item, asset_server: &AssetServer) -> Self {
+        Self {
+            item,
+            texture: asset_server.load(item.texture_path()),
+        }
+    }
+}
+
+/// Extruded mesh and material of every sprite used so far, by texture
+#[derive(Resource, Default)]
+pub struct ItemMeshes(
+    HashMap<AssetId<Image>, (Handle<Mesh>, Handle<StandardMaterial>)>,
+);
+
+impl ItemMeshes {
+    pub fn get(
+        &self,
+        texture: impl Into<AssetId<Image>>,
+    ) -> Option<(Handle<Mesh>, Handle<StandardMaterial>)> {
+        self.0.get(&texture.into()).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Models without a mesh yet, or showing another item
+type PendingItemModels<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, &'static ItemModel),
+    Or<(Changed<ItemModel>, Without<Mesh3d>)>,
+>;
+
+/// Give [`ItemModel`]s the mesh of their sprite, extruding it the first
+/// time a texture is seen
+pub fn build_item_models(
+    mut commands: Commands,
+    images: Res<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut cache: ResMut<ItemMeshes>,
+    models: PendingItemModels,
+) {
+    for (entity, model) in &models {
+        let id = model.texture.id();
+        let built = cache.get(id).or_else(|| {
+            let image = images.get(id)?;
+            let built = (
+                meshes.add(extrude_sprite(image)),
+                materials.add(StandardMaterial {
+                    base_color_texture: Some(model.texture.clone()),
+                    alpha_mode: AlphaMode::Mask(ALPHA_CUTOFF),
+                    perceptual_roughness: 1.0,
+                    ..StandardMaterial::default()
+                }),
+            );
+            cache.0.insert(id, built.clone());
+            Some(built)
+        });
+
+        match built {
+            Some((mesh, material)) => {
+                commands
+                    .entity(entity)
+                    .insert((Mesh3d(mesh), MeshMaterial3d(material)));
+            }
+            // Still loading, keep the previous item out of sight meanwhile
+            None => {
+                commands
+                    .entity(entity)
+                    .remove::<(Mesh3d, MeshMaterial3d<StandardMaterial>)>();
+            }
+        }
+    }
+}
+
+/// Mesh of a sprite one pixel deep. The front and back are single quads
+/// cut out by the material's alpha mask; side faces are only added where
+/// an opaque pixel borders a transparent one or the edge.
+pub fn extrude_sprite(image: &Image) -> Mesh {
+    let (width, height) = (image.width(), image.height());
+    // Formats that can't be read back are drawn as fully opaque
+    let opaque = |x: i64, y: i64| {
+        x >= 0
+            && y >= 0
+            && x < width as i64
+            && y < height as i64
+            && image
+                .get_color_at(x as u32, y as u32)
+                .map_or(true, |color| color.alpha() >= ALPHA_CUTOFF)
+    };
+
+    let pixel = Vec3::new(1.0 / width as f32, 1.0 / height as f32, 0.0);
+    let depth = 0.5 / width as f32;
+    let mut builder = SpriteMeshBuilder::default();
+
+    builder.quad(
+        [
+            Vec3::new(-0.5, -0.5, depth),
+            Vec3::new(0.5, -0.5, depth),
+            Vec3::new(0.5, 0.5, depth),
+            Vec3::new(-0.5, 0.5, depth),
+        ],
+        Vec3::Z,
+        [[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]],
+    );
+    builder.quad(
+        [
+            Vec3::new(0.5, -0.5, -depth),
+            Vec3::new(-0.5, -0.5, -depth),
+            Vec3::new(-0.5, 0.5, -depth),
+            Vec3::new(0.5, 0.5, -depth),
+        ],
+        Vec3::NEG_Z,
+        [[1.0, 1.0], [0.0, 1.0], [0.0, 0.0], [1.0, 0.0]],
+    );
+
+    for y in 0..height as i64 {
+        for x in 0..width as i64 {
+            if !opaque(x, y) {
+                continue;
+            }
+            // Sides take the color of the pixel they belong to
+            let uv = [
+                (x as f32 + 0.5) / width as f32,
+                (y as f32 + 0.5) / height as f32,
+            ];
+            let min = Vec3::new(
+                -0.5 + x as f32 * pixel.x,
+                0.5 - (y + 1) as f32 * pixel.y,
+                -depth,
+            );
+            let max = Vec3::new(min.x + pixel.x, min.y + pixel.y, depth);
+
+            for (dx, dy, normal) in [
+                (-1, 0, Vec3::NEG_X),
+                (1, 0, Vec3::X),
+                (0, -1, Vec3::Y),
+                (0, 1, Vec3::NEG_Y),
+            ] {
+                if !opaque(x + dx, y + dy) {
+                    builder.side(min, max, normal, uv);
+                }
+            }
+        }
+    }
+
+    builder.build()
+}
+
+#[derive(Default)]
+struct SpriteMeshBuilder {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    indices: Vec<u32>,
+}
+
+impl SpriteMeshBuilder {
+    /// Add a quad whose corners go counterclockwise seen from `normal`
+    fn quad(&mut self, corners: [Vec3; 4], normal: Vec3, uvs: [[f32; 2]; 4]) {
+        let start = self.positions.len() as u32;
+        self.positions
+            .extend(corners.map(|corner| corner.to_array()));
+        self.normals.extend([normal.to_array(); 4]);
+        self.uvs.extend(uvs);
+        self.indices
+            .extend([0, 1, 2, 0, 2, 3].map(|index: u32| start + index));
+    }
+
+    /// Add the face of the pixel box `min`..`max` facing `normal`
+    fn side(&mut self, min: Vec3, max: Vec3, normal: Vec3, uv: [f32; 2]) {
+        // The face lies on the box's min or max plane along `normal`
+        let plane = Vec3::select(normal.cmpgt(Vec3::ZERO), max, min);
+        let corners = if normal.x != 0.0 {
+            [
+                Vec3::new(plane.x, min.y, min.z),
+                Vec3::new(plane.x, max.y, min.z),
+                Vec3::new(plane.x, max.y, max.z),
+                Vec3::new(plane.x, min.y, max.z),
+            ]
+        } else {
+            [
+                Vec3::new(min.x, plane.y, min.z),
+                Vec3::new(min.x, plane.y, max.z),
+                Vec3::new(max.x, plane.y, max.z),
+                Vec3::new(max.x, plane.y, min.z),
+            ]
+        };
+
+        // Wind the corners counterclockwise seen from `normal`
+        let winding = (corners[1] - corners[0]).cross(corners[2] - corners[0]);
+        let corners = if winding.dot(normal) < 0.0 {
+            [corners[0], corners[3], corners[2], corners[1]]
+        } else {
+            corners
+        };
+        self.quad(corners, normal, [uv; 4]);
+    }
+
+    fn build(self) -> Mesh {
+        Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, self.positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs)
+        .with_inserted_indices(Indices::U32(self.indices))
+    }
+}
@@ -6,19 +6,27 @@ pub mod built_block_mesh;
 pub mod bindless_material;
 pub mod gpu_fsc;
 pub mod light;
+pub mod settings;
+pub mod sounds;
 
 pub mod crosshair;
+pub mod hud;
+pub mod inventory;
 pub mod debug_screen;
 pub mod wireframe;
 pub mod raycast;
+pub mod mining;
+pub mod particle;
+pub mod atlas_definition;
 pub mod atlas_enum;
 pub mod gui_atlas;
 
 pub mod animation;
 pub mod block;
+pub mod item;
 pub mod chunk;
+pub mod schematic;
 pub mod model;
 pub mod entity;
-pub mod mob;
 
 pub mod components;
\ No newline at end of file
@@ -1,12 +1,27 @@
+use std::f32::consts::TAU;
+
 use bevy::{
+    asset::Assets,
     color::Color,
-    ecs::system::Commands,
-    math::{EulerRot, Quat},
-    pbr::{AmbientLight, DirectionalLight},
+    ecs::{
+        change_detection::DetectChanges,
+        component::Component,
+        event::{Event, EventReader, EventWriter},
+        query::{With, Without},
+        resource::Resource,
+        system::{Commands, Query, Res, ResMut},
+    },
+    input::{keyboard::KeyCode, ButtonInput},
+    math::{EulerRot, FloatExt, Quat, Vec3},
+    pbr::{AmbientLight, DirectionalLight, DistanceFog},
+    render::camera::ClearColor,
+    time::Time,
     transform::components::Transform,
     utils::default,
 };
 
+use crate::bindless_material::BindlessMaterial;
+
 pub fn setup_simple_light(mut commands: Commands) {
     commands.insert_resource(AmbientLight {
         color: Color::srgb(0.95, 0.95, 1.0), 
@@ -36,3 +51,251 @@ pub fn setup_simple_light(mut commands: Commands) {
     ));
 
 }
+
+/// Lowest sky light, reached at midnight
+const MIN_SKY_LIGHT: f32 = 0.25;
+const SUN_ILLUMINANCE: f32 = 5000.0;
+const MOON_ILLUMINANCE: f32 = 400.0;
+const DAY_AMBIENT_BRIGHTNESS: f32 = 200.0;
+const NIGHT_AMBIENT_BRIGHTNESS: f32 = 40.0;
+/// How far `[` and `]` move the clock, one hour
+const TIME_STEP: f32 = 1.0 / 24.0;
+
+/// Where the world is in its day. `time` runs from `0.0` to `1.0`:
+/// sunrise at `0.0`, noon at `0.25`, sunset at `0.5` and midnight at
+/// `0.75`.
+#[derive(Resource, Debug, Clone)]
+pub struct TimeOfDay {
+    pub time: f32,
+    /// Length of a full day in seconds
+    pub day_length: f32,
+    pub paused: bool,
+}
+
+impl Default for TimeOfDay {
+    fn default() -> Self {
+        Self {
+            time: 0.1,
+            // Same as Minecraft, 20 minutes
+            day_length: 1200.0,
+            paused: false,
+        }
+    }
+}
+
+impl TimeOfDay {
+    /// Angle of the sun above the eastern horizon
+    pub fn sun_angle(&self) -> f32 {
+        self.time * TAU
+    }
+
+    /// `1.0` in full daylight, `0.0` at night, blending around the horizon
+    pub fn daylight(&self) -> f32 {
+        smoothstep(-0.1, 0.25, self.sun_angle().sin())
+    }
+
+    /// Multiplier for the ambient light blocks receive from the sky. Direct
+    /// sun and moon light is dimmed by their illuminance instead.
+    pub fn sky_light(&self) -> f32 {
+        MIN_SKY_LIGHT + (1.0 - MIN_SKY_LIGHT) * self.daylight()
+    }
+
+    pub fn set(&mut self, time: f32) {
+        self.time = time.rem_euclid(1.0);
+    }
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub enum TimeCommand {
+    /// Jump to a time of day, see [`TimeOfDay::time`]
+    Set(f32),
+    /// Change the day length in seconds
+    SetDayLength(f32),
+    Pause,
+    Resume,
+    TogglePause,
+}
+
+#[derive(Component)]
+pub struct Sun;
+
+#[derive(Component)]
+pub struct Moon;
+
+pub fn setup_day_night(mut commands: Commands) {
+    commands.insert_resource(TimeOfDay::default());
+    commands.insert_resource(AmbientLight {
+        brightness: DAY_AMBIENT_BRIGHTNESS,
+        color: Color::srgb(0.8, 0.9, 1.0),
+        ..default()
+    });
+
+    commands.spawn((
+        DirectionalLight {
+            illuminance: SUN_ILLUMINANCE,
+            shadows_enabled: true,
+            color: Color::srgb(1.0, 0.95, 0.8),
+            ..default()
+        },
+        Transform::default(),
+        bevy::pbr::CascadeShadowConfigBuilder {
+            first_cascade_far_bound: 25.0,
+            maximum_distance: 200.0,
+            ..default()
+        }
+        .build(),
+        Sun,
+    ));
+
+    commands.spawn((
+        DirectionalLight {
+            illuminance: 0.0,
+            shadows_enabled: false,
+            color: Color::srgb(0.6, 0.7, 1.0),
+            ..default()
+        },
+        Transform::default(),
+        Moon,
+    ));
+}
+
+/// `T` pauses the clock, `[` and `]` move it back and forth by an hour
+pub fn time_keyboard_commands(
+    keys: Res<ButtonInput<KeyCode>>,
+    time_of_day: Res<TimeOfDay>,
+    mut commands: EventWriter<TimeCommand>,
+) {
+    if keys.just_pressed(KeyCode::KeyT) {
+        commands.write(TimeCommand::TogglePause);
+    }
+    if keys.just_pressed(KeyCode::BracketLeft) {
+        commands.write(TimeCommand::Set(time_of_day.time - TIME_STEP));
+    }
+    if keys.just_pressed(KeyCode::BracketRight) {
+        commands.write(TimeCommand::Set(time_of_day.time + TIME_STEP));
+    }
+}
+
+pub fn handle_time_commands(
+    mut events: EventReader<TimeCommand>,
+    mut time_of_day: ResMut<TimeOfDay>,
+) {
+    for event in events.read() {
+        match *event {
+            TimeCommand::Set(time) => time_of_day.set(time),
+            TimeCommand::SetDayLength(seconds) => {
+                time_of_day.day_length = seconds.max(1.0);
+            }
+            TimeCommand::Pause => time_of_day.paused = true,
+            TimeCommand::Resume => time_of_day.paused = false,
+            TimeCommand::TogglePause => {
+                time_of_day.paused = !time_of_day.paused;
+            }
+        }
+    }
+}
+
+pub fn advance_time_of_day(
+    time: Res<Time>,
+    mut time_of_day: ResMut<TimeOfDay>,
+) {
+    if time_of_day.paused {
+        return;
+    }
+    let time = time_of_day.time + time.delta_secs() / time_of_day.day_length;
+    time_of_day.set(time);
+}
+
+type CelestialLight<'a> = (&'a mut Transform, &'a mut DirectionalLight);
+
+/// Move the sun and moon across the sky and tint the ambient light, sky
+/// and fog to match
+pub fn update_sun_and_moon(
+    time_of_day: Res<TimeOfDay>,
+    mut ambient: ResMut<AmbientLight>,
+    mut clear_color: ResMut<ClearColor>,
+    mut sun: Query<CelestialLight, With<Sun>>,
+    mut moon: Query<CelestialLight, (With<Moon>, Without<Sun>)>,
+    mut fogs: Query<&mut DistanceFog>,
+) {
+    if !time_of_day.is_changed() {
+        return;
+    }
+
+    let angle = time_of_day.sun_angle();
+    let daylight = time_of_day.daylight();
+    // Tilted a little so the sun never passes straight overhead
+    let sun_position = Vec3::new(angle.cos(), angle.sin(), 0.3).normalize();
+    // Warm near the horizon, white at noon
+    let elevation = sun_position.y.clamp(0.0, 1.0);
+
+    if let Ok((mut transform, mut light)) = sun.single_mut() {
+        *transform = Transform::from_translation(sun_position)
+            .looking_at(Vec3::ZERO, Vec3::Y);
+        light.illuminance = SUN_ILLUMINANCE * daylight;
+        light.color = rgb(
+            Vec3::new(1.0, 0.6, 0.35)
+                .lerp(Vec3::new(1.0, 0.95, 0.8), elevation.sqrt()),
+        );
+    }
+    if let Ok((mut transform, mut light)) = moon.single_mut() {
+        *transform = Transform::from_translation(-sun_position)
+            .looking_at(Vec3::ZERO, Vec3::Y);
+        light.illuminance = MOON_ILLUMINANCE * (1.0 - daylight);
+    }
+
+    ambient.brightness = NIGHT_AMBIENT_BRIGHTNESS.lerp(
+        DAY_AMBIENT_BRIGHTNESS,
+        daylight,
+    );
+    ambient.color =
+        rgb(Vec3::new(0.3, 0.35, 0.6).lerp(Vec3::new(0.8, 0.9, 1.0), daylight));
+
+    let sky = sky_color(daylight, elevation);
+    clear_color.0 = sky;
+    for mut fog in &mut fogs {
+        fog.color = sky;
+    }
+}
+
+/// Copy the sky light into every chunk material that is out of date,
+/// including chunks spawned since the last change
+pub fn update_sky_light(
+    time_of_day: Res<TimeOfDay>,
+    mut materials: ResMut<Assets<BindlessMaterial>>,
+) {
+    let sky_light = time_of_day.sky_light();
+    // Every change rebuilds the bind group, skip the tiny ones
+    let stale: Vec<_> = materials
+        .iter()
+        .filter(|(_, material)| {
+            (material.uniforms.sky_light - sky_light).abs() > 0.01
+        })
+        .map(|(id, _)| id)
+        .collect();
+
+    for id in stale {
+        if let Some(material) = materials.get_mut(id) {
+            material.uniforms.sky_light = sky_light;
+        }
+    }
+}
+
+fn sky_color(daylight: f32, elevation: f32) -> Color {
+    let night = Vec3::new(0.02, 0.02, 0.06);
+    let day = Vec3::new(0.47, 0.65, 1.0);
+    let sunset = Vec3::new(0.9, 0.5, 0.3);
+
+    // Orange while the sun is low but up, fading into blue as it rises
+    let horizon_glow = daylight * (1.0 - (elevation * 4.0).min(1.0));
+    rgb(night.lerp(day, daylight).lerp(sunset, horizon_glow * 0.6))
+}
+
+fn rgb(color: Vec3) -> Color {
+    Color::srgb(color.x, color.y, color.z)
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
@@ -0,0 +1,251 @@
+use bevy::{
+    asset::{Assets, Handle, RenderAssetUsages},
+    color::Color,
+    ecs::{
+        component::Component,
+        event::EventWriter,
+        query::With,
+        resource::Resource,
+        system::{Commands, Query, Res, ResMut},
+    },
+    input::{mouse::MouseButton, ButtonInput},
+    math::{primitives::Rectangle, Quat, Vec3},
+    pbr::{MeshMaterial3d, StandardMaterial},
+    render::{
+        alpha::AlphaMode,
+        mesh::{Mesh, Mesh3d},
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        view::Visibility,
+    },
+    time::Time,
+    transform::components::Transform,
+    utils::default,
+};
+use bevy_image::Image;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    block::{BlockRegistry, BuiltBlockID},
+    chunk::{BlockChangeCause, SetBlockEvent, WorldPos, WorldReader},
+    raycast::RaycastDebugInfo,
+};
+
+/// Number of destroy stages shown while a block is being broken
+pub const CRACK_STAGES: usize = 10;
+
+const CRACK_TEXTURE_SIZE: u32 = 16;
+
+/// Pause after a block breaks before the next one starts
+const BREAK_COOLDOWN: f32 = 0.25;
+
+/// Keeps the overlay from z-fighting with the block face
+const OVERLAY_OFFSET: f32 = 0.002;
+
+#[derive(Resource, Default)]
+pub struct BlockBreaking {
+    pub target: Option<WorldPos>,
+    pub face_normal: Vec3,
+    /// `0.0..1.0`, the block breaks when this reaches `1.0`
+    pub progress: f32,
+    cooldown: f32,
+}
+
+impl BlockBreaking {
+    pub fn stage(&self) -> usize {
+        ((self.progress * CRACK_STAGES as f32) as usize).min(CRACK_STAGES - 1)
+    }
+}
+
+#[derive(Component)]
+pub struct CrackOverlay;
+
+#[derive(Resource)]
+pub struct CrackOverlayMaterials(pub Vec<Handle<StandardMaterial>>);
+
+pub fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let stages: Vec<_> = crack_stage_images()
+        .into_iter()
+        .map(|image| {
+            materials.add(StandardMaterial {
+                base_color: Color::WHITE,
+                base_color_texture: Some(images.add(image)),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            })
+        })
+        .collect();
+
+    commands.spawn((
+        Mesh3d(meshes.add(Rectangle::new(1.0, 1.0))),
+        MeshMaterial3d(stages[0].clone()),
+        Transform::default(),
+        Visibility::Hidden,
+        CrackOverlay,
+    ));
+
+    commands.insert_resource(CrackOverlayMaterials(stages));
+    commands.insert_resource(BlockBreaking::default());
+}
+
+/// Advance breaking progress on the targeted block while the left mouse
+/// button is held. Progress restarts whenever the target changes.
+pub fn update_block_breaking(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    time: Res<Time>,
+    raycast: Res<RaycastDebugInfo>,
+    reader: WorldReader,
+    breg: Res<BlockRegistry>,
+    mut breaking: ResMut<BlockBreaking>,
+    mut set_block_events: EventWriter<SetBlockEvent>,
+) {
+    breaking.cooldown = (breaking.cooldown - time.delta_secs()).max(0.0);
+
+    let target = raycast
+        .last_hit
+        .filter(|_| mouse_input.pressed(MouseButton::Left));
+
+    if target != breaking.target {
+        breaking.target = target;
+        breaking.progress = 0.0;
+    }
+    breaking.face_normal = raycast.hit_face_normal.unwrap_or(Vec3::Y);
+
+    let Some(world_pos) = target else {
+        return;
+    };
+    if breaking.cooldown > 0.0 {
+        return;
+    }
+    let Some(block) = reader.get_block(world_pos).block() else {
+        return;
+    };
+
+    let break_time = breg.break_time(block);
+    if break_time > 0.0 {
+        breaking.progress += time.delta_secs() / break_time;
+    } else {
+        breaking.progress = 1.0;
+    }
+
+    if breaking.progress >= 1.0 {
+        set_block_events.write(SetBlockEvent {
+            world_pos,
+            block: BuiltBlockID::Air,
+            cause: BlockChangeCause::PlayerBreak,
+        });
+
+        breaking.progress = 0.0;
+        breaking.cooldown = BREAK_COOLDOWN;
+    }
+}
+
+pub fn update_crack_overlay(
+    breaking: Res<BlockBreaking>,
+    stages: Res<CrackOverlayMaterials>,
+    mut overlay: Query<
+        (
+            &mut Transform,
+            &mut Visibility,
+            &mut MeshMaterial3d<StandardMaterial>,
+        ),
+        With<CrackOverlay>,
+    >,
+) {
+    let Ok((mut transform, mut visibility, mut material)) =
+        overlay.single_mut()
+    else {
+        return;
+    };
+
+    let Some(world_pos) = breaking.target.filter(|_| breaking.progress > 0.0)
+    else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+
+    let normal = if breaking.face_normal == Vec3::ZERO {
+        Vec3::Y
+    } else {
+        breaking.face_normal
+    };
+    let center =
+        Vec3::new(world_pos.x as f32, world_pos.y as f32, world_pos.z as f32);
+
+    transform.translation = center + normal * (0.5 + OVERLAY_OFFSET);
+    transform.rotation = Quat::from_rotation_arc(Vec3::Z, normal);
+    material.0 = stages.0[breaking.stage()].clone();
+    *visibility = Visibility::Visible;
+}
+
+/// Crack textures for every destroy stage. Cracks grow outward from the
+/// center, each stage adding pixels on top of the previous one.
+pub fn crack_stage_images() -> Vec<Image> {
+    let size = CRACK_TEXTURE_SIZE as i32;
+    let mut rng = StdRng::seed_from_u64(0x000c_7ac5);
+
+    const DIRECTIONS: [(i32, i32); 8] = [
+        (1, 0),
+        (1, 1),
+        (0, 1),
+        (-1, 1),
+        (-1, 0),
+        (-1, -1),
+        (0, -1),
+        (1, -1),
+    ];
+
+    let mut branches: Vec<_> = (0..6)
+        .map(|_| {
+            let direction = DIRECTIONS[rng.gen_range(0..DIRECTIONS.len())];
+            ((size / 2, size / 2), direction)
+        })
+        .collect();
+
+    // Walk all branches in lockstep so early stages stay near the center
+    let mut pixels = vec![(size / 2, size / 2)];
+    for _ in 0..size {
+        for ((x, y), (dx, dy)) in branches.iter_mut() {
+            *x += *dx;
+            *y += *dy;
+            if rng.gen_bool(0.3) {
+                *x += rng.gen_range(-1..=1);
+                *y += rng.gen_range(-1..=1);
+            }
+            if (0..size).contains(x)
+                && (0..size).contains(y)
+                && !pixels.contains(&(*x, *y))
+            {
+                pixels.push((*x, *y));
+            }
+        }
+    }
+
+    (1..=CRACK_STAGES)
+        .map(|stage| {
+            let mut data = vec![0u8; (size * size * 4) as usize];
+            let shown = pixels.len() * stage / CRACK_STAGES;
+            for &(x, y) in &pixels[..shown] {
+                let i = ((y * size + x) * 4) as usize;
+                data[i..i + 4].copy_from_slice(&[24, 24, 24, 200]);
+            }
+
+            Image::new(
+                Extent3d {
+                    width: CRACK_TEXTURE_SIZE,
+                    height: CRACK_TEXTURE_SIZE,
+                    depth_or_array_layers: 1,
+                },
+                TextureDimension::D2,
+                data,
+                TextureFormat::Rgba8UnormSrgb,
+                RenderAssetUsages::RENDER_WORLD,
+            )
+        })
+        .collect()
+}
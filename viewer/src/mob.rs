@@ -1,4 +0,0 @@
-
-pub mod villager;
-pub mod skeleton;
-pub mod zombie;
@@ -1,17 +1,21 @@
-use std::{collections::HashMap, time::Duration};
+use std::{collections::HashMap, iter, time::Duration};
 
 use bevy::{
     animation::{
         graph::{AnimationGraph, AnimationGraphHandle, AnimationNodeIndex},
         transition::AnimationTransitions,
-        AnimationClip, AnimationPlayer,
+        ActiveAnimation, AnimationClip, AnimationPlayer, RepeatAnimation,
     },
     asset::{Assets, Handle},
     ecs::{
+        change_detection::{DetectChanges, DetectChangesMut},
+        component::Component,
         entity::Entity,
-        hierarchy::Children,
+        event::EventReader,
+        hierarchy::{ChildOf, Children},
         observer::Trigger,
-        query::Without,
+        query::{Added, With, Without},
+        removal_detection::RemovedComponents,
         resource::Resource,
         system::{Commands, Query, Res},
     },
@@ -19,208 +23,569 @@ use bevy::{
     scene::SceneInstanceReady,
 };
 
-use crate::animation::{AnimationConfig, AnimationConfigs, ModelAnimation};
+use crate::{
+    animation::{
+        AnimationConfig, AnimationConfigs, AnimationOwner, BlendWeights,
+        ModelAnimation, PlayAnimation,
+    },
+    model::{manifest::Model, mask::BoneMasks},
+};
 
-#[derive(Resource)]
-pub struct AnimationAssets {
-    pub basic_graph: Handle<AnimationGraph>,
-    pub basic_animations: HashMap<String, AnimationNodeIndex>,
-    pub blend_graphs:
-        HashMap<String, (Handle<AnimationGraph>, Vec<AnimationNodeIndex>)>,
-    pending_animations: Vec<(String, Handle<AnimationClip>)>,
+/// Cross-fade used when a [`ModelAnimation`] is changed directly
+pub const DEFAULT_FADE: Duration = Duration::from_millis(250);
+
+enum AnimationEntry {
+    Clip(Handle<AnimationClip>, Option<String>),
+    Blend(Vec<(String, Handle<AnimationClip>, Option<String>)>),
 }
 
-impl AnimationAssets {
-    pub fn new() -> Self {
-        Self {
-            basic_graph: Handle::default(),
-            basic_animations: HashMap::new(),
-            blend_graphs: HashMap::new(),
-            pending_animations: Vec::new(),
-        }
-    }
+/// Graph nodes of a blend
+#[derive(Debug, Clone)]
+pub struct BlendNodes {
+    /// Empty node played in place of the blend, only there to carry the
+    /// weight `AnimationTransitions` fades, see [`ActiveBlends`]
+    pub fade: AnimationNodeIndex,
+    /// Node of every animation in the blend
+    pub clips: Vec<(String, AnimationNodeIndex)>,
+}
+
+/// The graph of one model type. Single clips and the clips of every blend
+/// all sit directly under the root, so `AnimationTransitions` can fade
+/// between any two of them.
+#[derive(Default)]
+pub struct ModelAnimations {
+    pub graph: Handle<AnimationGraph>,
+    /// Node of every single clip
+    pub nodes: HashMap<String, AnimationNodeIndex>,
+    pub blends: HashMap<String, BlendNodes>,
+    masks: BoneMasks,
+    /// Every registered animation in graph order
+    entries: Vec<(String, AnimationEntry)>,
+    /// Animations were registered since the graph was last built
+    dirty: bool,
+}
 
+impl ModelAnimations {
+    /// Registering a name again replaces its clip in place, so node indices
+    /// handed out earlier stay valid. With a `mask` the clip only drives the
+    /// bones of that mask.
     pub fn register_animation(
         &mut self,
         name: String,
         clip: Handle<AnimationClip>,
+        mask: Option<String>,
     ) {
-        self.pending_animations.push((name, clip));
+        self.register(name, AnimationEntry::Clip(clip, mask));
     }
 
-    pub fn finalize_basic_graph(
+    /// Register a blend of named, optionally masked clips. Like
+    /// [`Self::register_animation`], indices stay valid as long as the
+    /// number of clips stays the same.
+    pub fn register_blend(
         &mut self,
-        graphs: &mut Assets<AnimationGraph>,
+        name: String,
+        clips: Vec<(String, Handle<AnimationClip>, Option<String>)>,
     ) {
-        if !self.pending_animations.is_empty() {
-            let clips: Vec<_> = self
-                .pending_animations
-                .iter()
-                .map(|(_, clip)| clip.clone())
-                .collect();
-            let (basic_graph, basic_node_indices) =
-                AnimationGraph::from_clips(clips);
+        self.register(name, AnimationEntry::Blend(clips));
+    }
 
-            for (i, (name, _)) in self.pending_animations.iter().enumerate() {
-                self.basic_animations
-                    .insert(name.clone(), basic_node_indices[i]);
+    fn register(&mut self, name: String, entry: AnimationEntry) {
+        match self
+            .entries
+            .iter_mut()
+            .find(|(existing, _)| *existing == name)
+        {
+            Some(existing) => existing.1 = entry,
+            None => self.entries.push((name, entry)),
+        }
+        self.dirty = true;
+    }
+
+    pub fn set_masks(&mut self, masks: BoneMasks) {
+        self.masks = masks;
+        self.dirty = true;
+    }
+
+    pub fn clip(&self, name: &str) -> Option<&Handle<AnimationClip>> {
+        self.entries
+            .iter()
+            .find_map(|(existing, entry)| match entry {
+                AnimationEntry::Clip(clip, _) if existing == name => Some(clip),
+                _ => None,
+            })
+    }
+
+    /// Rebuild the graph if animations were registered since the last
+    /// call. Manifests can arrive at any time, so this runs more than once.
+    pub fn finalize_graph(&mut self, graphs: &mut Assets<AnimationGraph>) {
+        if !self.dirty {
+            return;
+        }
+
+        let mut graph = AnimationGraph::new();
+        let root = graph.root;
+        self.masks.apply(&mut graph);
+        self.nodes.clear();
+        self.blends.clear();
+
+        for (name, entry) in &self.entries {
+            match entry {
+                AnimationEntry::Clip(clip, mask) => {
+                    let node = graph.add_clip_with_mask(
+                        clip.clone(),
+                        self.masks.only(mask.as_deref()),
+                        1.0,
+                        root,
+                    );
+                    self.nodes.insert(name.clone(), node);
+                }
+                AnimationEntry::Blend(clips) => {
+                    let fade = graph.add_blend(1.0, root);
+                    let clips = clips
+                        .iter()
+                        .map(|(animation, clip, mask)| {
+                            let node = graph.add_clip_with_mask(
+                                clip.clone(),
+                                self.masks.only(mask.as_deref()),
+                                1.0,
+                                root,
+                            );
+                            (animation.clone(), node)
+                        })
+                        .collect();
+                    self.blends
+                        .insert(name.clone(), BlendNodes { fade, clips });
+                }
             }
+        }
+
+        self.graph = graphs.add(graph);
+        self.dirty = false;
+    }
+}
 
-            self.basic_graph = graphs.add(basic_graph);
-            self.pending_animations.clear();
+/// [`ModelAnimations`] by entity type, so models can reuse animation names
+#[derive(Resource, Default)]
+pub struct AnimationAssets {
+    models: HashMap<String, ModelAnimations>,
+}
+
+impl AnimationAssets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn model(&self, entity_type: &str) -> Option<&ModelAnimations> {
+        self.models.get(entity_type)
+    }
+
+    pub fn model_mut(&mut self, entity_type: &str) -> &mut ModelAnimations {
+        self.models.entry(entity_type.to_string()).or_default()
+    }
+
+    /// See [`ModelAnimations::finalize_graph`]
+    pub fn finalize_graphs(&mut self, graphs: &mut Assets<AnimationGraph>) {
+        for animations in self.models.values_mut() {
+            animations.finalize_graph(graphs);
         }
     }
 }
 
+struct ActiveBlend {
+    fade: AnimationNodeIndex,
+    /// Animation name, node and configured weight of every clip
+    clips: Vec<(String, AnimationNodeIndex, f32)>,
+}
+
+/// Blends a player is playing or fading out. `AnimationTransitions` fades
+/// the empty [`BlendNodes::fade`] node, [`sync_blend_weights`] spreads its
+/// weight over the clips.
+#[derive(Component, Default)]
+pub struct ActiveBlends(Vec<ActiveBlend>);
+
+/// Animation players not handed a [`ModelAnimation`] yet
+type UnsetPlayers<'w, 's> =
+    Query<'w, 's, Entity, (With<AnimationPlayer>, Without<ModelAnimation>)>;
+
+/// Hand the [`ModelAnimation`] of a model to the animation players in its
+/// scene, along with the [`AnimationOwner`] it is resolved against
 pub fn setup_animation(
     trigger: Trigger<SceneInstanceReady>,
     mut commands: Commands,
     animation_assets: Res<AnimationAssets>,
-    animation_configs: Res<AnimationConfigs>,
-    mut players: Query<
-        (Entity, &mut AnimationPlayer),
-        Without<ModelAnimation>,
-    >,
-    entity_animation: Query<&ModelAnimation>,
+    players: UnsetPlayers,
+    models: Query<(&Model, &ModelAnimation)>,
     children: Query<&Children>,
 ) {
-    let Ok(entity_animation) = entity_animation.get(trigger.target()) else {
+    let Ok((model, entity_animation)) = models.get(trigger.target()) else {
         return;
     };
+    let entity_type = &model.entity_type;
 
-    let Some(config) = animation_configs.0.get(&entity_animation.0)
-    else {
-        error!(
-            "Animation config '{}' not found!",
-            entity_animation.0
-        );
+    let Some(animations) = animation_assets.model(entity_type) else {
+        error!("No animations registered for model '{}'!", entity_type);
         return;
     };
 
-    for descendant in children.iter_descendants(trigger.target()) {
-        if let Ok((entity, _player)) = players.get_mut(descendant) {
-            commands.entity(entity).insert(entity_animation.clone());
+    set_up_players(
+        &mut commands,
+        &players,
+        &children,
+        trigger.target(),
+        entity_type,
+        animations,
+        entity_animation,
+    );
+}
 
-            match config {
-                AnimationConfig::Single { .. } => {
-                    commands.entity(entity).insert(AnimationGraphHandle(
-                        animation_assets.basic_graph.clone(),
-                    ));
-                }
-                AnimationConfig::Blend { .. } => {
-                    if let Some((blend_graph_handle, _)) = animation_assets
-                        .blend_graphs
-                        .get(&entity_animation.0)
-                    {
-                        commands.entity(entity).insert(AnimationGraphHandle(
-                            blend_graph_handle.clone(),
-                        ));
-                    }
-                }
-            }
+/// Same as [`setup_animation`] for models given a [`ModelAnimation`] after
+/// their scene was ready, such as a rider without a default animation.
+/// Scenes that aren't ready yet have no players and are left to the
+/// observer.
+pub fn setup_added_animations(
+    mut commands: Commands,
+    animation_assets: Res<AnimationAssets>,
+    players: UnsetPlayers,
+    models: Query<(Entity, &Model, &ModelAnimation), Added<ModelAnimation>>,
+    children: Query<&Children>,
+) {
+    for (entity, model, entity_animation) in &models {
+        let entity_type = &model.entity_type;
+        let Some(animations) = animation_assets.model(entity_type) else {
+            continue;
+        };
+        set_up_players(
+            &mut commands,
+            &players,
+            &children,
+            entity,
+            entity_type,
+            animations,
+            entity_animation,
+        );
+    }
+}
+
+fn set_up_players(
+    commands: &mut Commands,
+    players: &UnsetPlayers,
+    children: &Query<&Children>,
+    root: Entity,
+    entity_type: &str,
+    animations: &ModelAnimations,
+    entity_animation: &ModelAnimation,
+) {
+    for descendant in children.iter_descendants(root) {
+        if let Ok(entity) = players.get(descendant) {
+            commands.entity(entity).insert((
+                entity_animation.clone(),
+                AnimationOwner(entity_type.to_string()),
+                AnimationGraphHandle(animations.graph.clone()),
+            ));
 
             debug!(
-                "Setup animation '{}' for entity {:?}",
-                entity_animation.0, entity
+                "Setup animation '{}' of '{}' for entity {:?}",
+                entity_animation.0, entity_type, entity
             );
         }
     }
 }
 
+/// Stop the players of models whose [`ModelAnimation`] was removed. They are
+/// set up again by [`setup_added_animations`] once the model gets a new one.
+pub fn stop_removed_animations(
+    mut commands: Commands,
+    mut removed: RemovedComponents<ModelAnimation>,
+    models: Query<(), (With<Model>, Without<ModelAnimation>)>,
+    mut players: Query<&mut AnimationPlayer, With<AnimationOwner>>,
+    children: Query<&Children>,
+) {
+    for entity in removed.read() {
+        // Despawned, given another animation, or one of the players
+        if models.get(entity).is_err() {
+            continue;
+        }
+        for descendant in children.iter_descendants(entity) {
+            if let Ok(mut player) = players.get_mut(descendant) {
+                player.stop_all();
+                commands.entity(descendant).remove::<(
+                    ModelAnimation,
+                    AnimationTransitions,
+                    ActiveBlends,
+                )>();
+                debug!("Stopped animations of entity {:?}", descendant);
+            }
+        }
+    }
+}
+
+/// Start the first animation of newly set up players
 pub fn update_animations(
     mut commands: Commands,
     animation_assets: Res<AnimationAssets>,
     animation_configs: Res<AnimationConfigs>,
     mut players: Query<
-        (Entity, &mut AnimationPlayer, &ModelAnimation),
+        (
+            Entity,
+            &mut AnimationPlayer,
+            &ModelAnimation,
+            &AnimationOwner,
+        ),
         Without<AnimationTransitions>,
     >,
 ) {
-    for (entity, mut player, entity_animation) in &mut players {
-        let Some(config) =
-            animation_configs.0.get(&entity_animation.0)
+    for (entity, mut player, entity_animation, owner) in &mut players {
+        let Some(config) = animation_configs.get(&owner.0, &entity_animation.0)
         else {
             continue;
         };
+        let Some(animations) = animation_assets.model(&owner.0) else {
+            continue;
+        };
 
-        match config {
-            AnimationConfig::Single {
-                speed,
-                repeat,
-                paused,
-                ..
-            } => {
-                let Some(&animation_node) = animation_assets
-                    .basic_animations
-                    .get(&entity_animation.0)
-                else {
-                    error!(
-                        "Animation '{}' not found in basic_animations! Available: {:?}",
-                        entity_animation.0,
-                        animation_assets.basic_animations.keys().collect::<Vec<_>>()
-                    );
-                    continue;
-                };
+        let mut transitions = AnimationTransitions::new();
+        let mut blends = ActiveBlends::default();
+        if !play_animation(
+            &mut player,
+            &mut transitions,
+            &mut blends,
+            animations,
+            config,
+            &entity_animation.0,
+            Duration::ZERO,
+        ) {
+            error!(
+                "Animation '{}' not found for model '{}'! Available: {:?}",
+                entity_animation.0,
+                owner.0,
+                animations.nodes.keys().collect::<Vec<_>>()
+            );
+            continue;
+        }
+        commands.entity(entity).insert((transitions, blends));
 
-                let mut transitions = AnimationTransitions::new();
-                let animation = transitions.play(
-                    &mut player,
-                    animation_node,
-                    Duration::ZERO,
-                );
+        info!(
+            "Started animation '{}' for entity {:?}",
+            entity_animation.0, entity
+        );
+    }
+}
 
-                animation.set_speed(*speed);
+/// Cross-fade to another animation when the [`ModelAnimation`] of a model
+/// changes or a [`PlayAnimation`] event arrives
+pub fn switch_animations(
+    mut events: EventReader<PlayAnimation>,
+    animation_assets: Res<AnimationAssets>,
+    animation_configs: Res<AnimationConfigs>,
+    mut models: Query<(Entity, &mut ModelAnimation), With<Model>>,
+    mut players: Query<
+        (
+            &mut AnimationPlayer,
+            &mut AnimationTransitions,
+            &mut ActiveBlends,
+            &mut ModelAnimation,
+            &AnimationOwner,
+        ),
+        Without<Model>,
+    >,
+    children: Query<&Children>,
+) {
+    let mut requests: Vec<_> = models
+        .iter_mut()
+        .filter(|(_, animation)| {
+            animation.is_changed() && !animation.is_added()
+        })
+        .map(|(entity, animation)| (entity, animation.0.clone(), DEFAULT_FADE))
+        .collect();
+    requests.extend(
+        events
+            .read()
+            .map(|event| (event.entity, event.name.clone(), event.fade)),
+    );
 
-                if *repeat {
-                    animation.repeat();
-                }
+    for (entity, name, fade) in requests {
+        // Keep the model's component in step with events. Its players are
+        // already playing `name` when the change is seen next frame.
+        if let Ok((_, mut animation)) = models.get_mut(entity) {
+            animation.set_if_neq(ModelAnimation(name.clone()));
+        }
 
-                if *paused {
-                    player.pause_all();
-                } else {
-                    player.resume_all();
-                }
+        for player_entity in
+            iter::once(entity).chain(children.iter_descendants(entity))
+        {
+            let Ok((
+                mut player,
+                mut transitions,
+                mut blends,
+                mut current,
+                owner,
+            )) = players.get_mut(player_entity)
+            else {
+                continue;
+            };
+            if current.0 == name {
+                continue;
+            }
 
-                commands.entity(entity).insert(transitions);
+            let (Some(config), Some(animations)) = (
+                animation_configs.get(&owner.0, &name),
+                animation_assets.model(&owner.0),
+            ) else {
+                error!(
+                    "Animation '{}' not found for model '{}'!",
+                    name, owner.0
+                );
+                continue;
+            };
 
-                info!(
-                    "Started single animation '{}' for entity {:?} (speed: {}, repeat: {})",
-                    entity_animation.0, entity, speed, repeat
+            if play_animation(
+                &mut player,
+                &mut transitions,
+                &mut blends,
+                animations,
+                config,
+                &name,
+                fade,
+            ) {
+                current.0 = name.clone();
+                debug!(
+                    "Fading to animation '{}' for entity {:?} over {:?}",
+                    name, player_entity, fade
                 );
             }
-            AnimationConfig::Blend {
-                speed,
-                repeat,
-                paused,
-                ..
-            } => {
-                if let Some((_, clip_indices)) = animation_assets
-                    .blend_graphs
-                    .get(&entity_animation.0)
-                {
-                    for &clip_node_index in clip_indices {
-                        let animation = player.play(clip_node_index);
-                        animation.set_speed(*speed);
-
-                        if *repeat {
-                            animation.repeat();
-                        }
-                    }
+        }
+    }
+}
 
-                    if *paused {
-                        player.pause_all();
-                    } else {
-                        player.resume_all();
+/// Play `name` on a player, fading out what it played before over `fade`.
+/// Returns `false` if the model's graph has no such animation.
+fn play_animation(
+    player: &mut AnimationPlayer,
+    transitions: &mut AnimationTransitions,
+    blends: &mut ActiveBlends,
+    animations: &ModelAnimations,
+    config: &AnimationConfig,
+    name: &str,
+    fade: Duration,
+) -> bool {
+    let (main, blend) = match config {
+        AnimationConfig::Single { .. } => match animations.nodes.get(name) {
+            Some(&node) => (node, None),
+            None => return false,
+        },
+        AnimationConfig::Blend {
+            animations: weights,
+            ..
+        } => match animations.blends.get(name) {
+            Some(nodes) => (nodes.fade, Some((nodes, weights))),
+            None => return false,
+        },
+    };
+    let (AnimationConfig::Single {
+        speed,
+        repeat,
+        paused,
+        ..
+    }
+    | AnimationConfig::Blend {
+        speed,
+        repeat,
+        paused,
+        ..
+    }) = *config;
+
+    // AnimationTransitions never fades out a paused clip, freeze it instead
+    if let Some(current) = transitions.get_main_animation() {
+        if let Some(animation) = player.animation_mut(current) {
+            if animation.is_paused() {
+                animation.set_speed(0.0).resume();
+            }
+        }
+        for blend in blends.0.iter().filter(|blend| blend.fade == current) {
+            for &(_, node, _) in &blend.clips {
+                if let Some(animation) = player.animation_mut(node) {
+                    if animation.is_paused() {
+                        animation.set_speed(0.0).resume();
                     }
+                }
+            }
+        }
+    }
 
-                    commands.entity(entity).insert(AnimationTransitions::new());
+    let configure = |animation: &mut ActiveAnimation| {
+        animation.set_speed(speed).set_repeat(if repeat {
+            RepeatAnimation::Forever
+        } else {
+            RepeatAnimation::Never
+        });
+        if paused {
+            animation.pause();
+        } else {
+            animation.resume();
+        }
+    };
 
-                    info!(
-                        "Started blend animation '{}' for entity {:?} (speed: {}, repeat: {})",
-                        entity_animation.0, entity, speed, repeat
-                    );
+    configure(transitions.play(player, main, fade));
+    blends.0.retain(|blend| blend.fade != main);
+
+    if let Some((nodes, weights)) = blend {
+        let clips = nodes
+            .clips
+            .iter()
+            .map(|(animation, node)| {
+                // Weight zero until the first `sync_blend_weights`
+                configure(player.start(*node).set_weight(0.0));
+                let weight = weights
+                    .iter()
+                    .find(|(name, _)| name == animation)
+                    .map_or(1.0, |&(_, weight)| weight);
+                (animation.clone(), *node, weight)
+            })
+            .collect();
+        blends.0.push(ActiveBlend { fade: main, clips });
+    }
+    true
+}
+
+/// Give the clips of every active blend their share of the blend's faded
+/// weight, using [`BlendWeights`] on the player or one of its ancestors
+/// over the configured weights. Clips of a blend that finished fading out
+/// are stopped.
+pub fn sync_blend_weights(
+    mut players: Query<(Entity, &mut AnimationPlayer, &mut ActiveBlends)>,
+    blend_weights: Query<&BlendWeights>,
+    parents: Query<&ChildOf>,
+) {
+    for (entity, mut player, mut blends) in &mut players {
+        let overrides = iter::once(entity)
+            .chain(parents.iter_ancestors(entity))
+            .find_map(|entity| blend_weights.get(entity).ok());
+
+        blends.0.retain(|blend| {
+            let fade = player.animation(blend.fade).map(|fade| fade.weight());
+            let weights: Vec<f32> = blend
+                .clips
+                .iter()
+                .map(|(animation, _, weight)| {
+                    overrides
+                        .and_then(|overrides| overrides.0.get(animation))
+                        .unwrap_or(weight)
+                        .max(0.0)
+                })
+                .collect();
+            let total: f32 = weights.iter().sum();
+
+            for (&(_, node, _), weight) in blend.clips.iter().zip(weights) {
+                let Some(fade) = fade else {
+                    player.stop(node);
+                    continue;
+                };
+                if let Some(animation) = player.animation_mut(node) {
+                    let share = if total > 0.0 { weight / total } else { 0.0 };
+                    animation.set_weight(fade * share);
                 }
             }
-        }
+            fade.is_some()
+        });
     }
 }
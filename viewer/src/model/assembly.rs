@@ -1,22 +1,274 @@
-use bevy::{math::Vec3, transform::components::Transform};
+use std::collections::HashMap;
+
+use bevy::{
+    asset::Assets,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::{Event, EventReader},
+        hierarchy::{ChildOf, Children},
+        name::Name,
+        observer::Trigger,
+        query::{Changed, Or, With, Without},
+        system::{Commands, Query, Res, SystemParam},
+    },
+    log::warn,
+    math::{Affine3A, Mat4, Vec3},
+    scene::SceneInstanceReady,
+    transform::components::{GlobalTransform, Transform},
+};
+use serde::Deserialize;
+
+use crate::model::manifest::{Model, ModelManifest, ModelManifests};
 
 pub type Pixel = f32;
 
-pub trait Localizable<Joint> {
-    fn position() -> Vec3;
+const PIXELS_PER_BLOCK: Pixel = 16.0;
+
+/// Attachment point of a model manifest
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct JointManifest {
+    /// glTF node the joint moves with, the model root if `None`
+    #[serde(default)]
+    pub bone: Option<String>,
+    /// Position in model pixels in the rest pose, the bone's origin if
+    /// `None`
+    #[serde(default)]
+    pub position: Option<(Pixel, Pixel, Pixel)>,
+}
+
+impl JointManifest {
+    pub fn position(&self) -> Option<Vec3> {
+        self.position.map(|(x, y, z)| Vec3::new(x, y, z))
+    }
 }
 
-pub struct Head;
-pub struct Body;
+/// Joints of a spawned model by name: the entity each one moves with and
+/// the joint's transform relative to it
+#[derive(Component, Debug, Clone, Default)]
+pub struct Joints(pub HashMap<String, (Entity, Transform)>);
+
+impl Joints {
+    pub fn get(&self, joint: &str) -> Option<(Entity, Transform)> {
+        self.0.get(joint).copied()
+    }
+}
+
+/// Keeps an entity on a joint of another model, following the joint's bone
+/// as it animates. Waits for the carrier's scene to spawn.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Attachment {
+    pub carrier: Entity,
+    pub joint: String,
+    /// Joint of the attached model put on the carrier's, its origin if
+    /// `None`
+    pub carried_joint: Option<String>,
+    /// Moves the attached entity off the joint, in blocks
+    pub offset: Vec3,
+}
+
+impl Attachment {
+    pub fn new(carrier: Entity, joint: &str) -> Self {
+        Self {
+            carrier,
+            joint: joint.to_string(),
+            carried_joint: None,
+            offset: Vec3::ZERO,
+        }
+    }
+
+    pub fn with_carried_joint(mut self, joint: &str) -> Self {
+        self.carried_joint = Some(joint.to_string());
+        self
+    }
+
+    pub fn with_offset(mut self, offset: Vec3) -> Self {
+        self.offset = offset;
+        self
+    }
+}
+
+/// Added once an [`Attachment`] is parented to its joint's bone
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Attached {
+    pub bone: Entity,
+}
+
+/// Attach an entity, or move it to another joint
+#[derive(Event, Debug, Clone)]
+pub struct AttachEntity {
+    pub entity: Entity,
+    pub attachment: Attachment,
+}
+
+/// Detach an entity, leaving it where it is
+#[derive(Event, Debug, Clone)]
+pub struct DetachEntity {
+    pub entity: Entity,
+}
+
+/// Looks up the bones of spawned models by glTF node name
+#[derive(SystemParam)]
+pub struct Bones<'w, 's> {
+    children: Query<'w, 's, &'static Children>,
+    names: Query<'w, 's, &'static Name>,
+}
+
+impl Bones<'_, '_> {
+    /// First entity named `bone` below `model`. Breadth first, so a bone is
+    /// found before a mesh of the same name below it.
+    pub fn find(&self, model: Entity, bone: &str) -> Option<Entity> {
+        self.children.iter_descendants(model).find(|&entity| {
+            self.names
+                .get(entity)
+                .is_ok_and(|name| name.as_str() == bone)
+        })
+    }
+}
+
+/// Build the [`Joints`] of a freshly spawned model from its manifest
+pub fn setup_joints(
+    trigger: Trigger<SceneInstanceReady>,
+    mut commands: Commands,
+    registry: Res<ModelManifests>,
+    manifests: Res<Assets<ModelManifest>>,
+    models: Query<&Model>,
+    bones: Bones,
+    transforms: Query<(&Transform, Option<&ChildOf>)>,
+) {
+    let model = trigger.target();
+    let Ok(model_info) = models.get(model) else {
+        return;
+    };
+    let Some(manifest) = registry.get(&manifests, &model_info.entity_type)
+    else {
+        return;
+    };
+
+    let mut joints = HashMap::new();
+    for (name, joint) in &manifest.joints {
+        let bone = match &joint.bone {
+            Some(bone) => {
+                let Some(entity) = bones.find(model, bone) else {
+                    warn!(
+                        "Model '{}' has no bone '{}' for joint '{}'",
+                        model_info.entity_type, bone, name
+                    );
+                    continue;
+                };
+                entity
+            }
+            None => model,
+        };
+
+        // The scene has not been animated yet, so this is the rest pose
+        let rest = rest_pose(model, bone, &transforms);
+        let point = joint
+            .position()
+            .map_or(rest.translation.into(), |position| {
+                position / PIXELS_PER_BLOCK
+            });
+        let offset = rest.inverse() * Affine3A::from_translation(point);
+        joints.insert(
+            name.clone(),
+            (bone, Transform::from_matrix(Mat4::from(offset))),
+        );
+    }
+    commands.entity(model).insert(Joints(joints));
+}
+
+/// Transform of `bone` relative to `model`
+fn rest_pose(
+    model: Entity,
+    bone: Entity,
+    transforms: &Query<(&Transform, Option<&ChildOf>)>,
+) -> Affine3A {
+    let mut pose = Affine3A::IDENTITY;
+    let mut entity = bone;
+    while entity != model {
+        let Ok((transform, Some(child_of))) = transforms.get(entity) else {
+            break;
+        };
+        pose = transform.compute_affine() * pose;
+        entity = child_of.parent();
+    }
+    pose
+}
+
+pub fn attach_entities(
+    mut commands: Commands,
+    mut attach_events: EventReader<AttachEntity>,
+    mut detach_events: EventReader<DetachEntity>,
+    attached: Query<&GlobalTransform, With<Attached>>,
+) {
+    for event in attach_events.read() {
+        commands
+            .entity(event.entity)
+            .insert(event.attachment.clone());
+    }
+    for event in detach_events.read() {
+        let Ok(mut entity) = commands.get_entity(event.entity) else {
+            continue;
+        };
+        entity.remove::<(Attachment, Attached)>();
+        if let Ok(global) = attached.get(event.entity) {
+            entity
+                .remove::<ChildOf>()
+                .insert(global.compute_transform());
+        }
+    }
+}
+
+/// Attachments not yet parented to their joint, or moved to another one
+type PendingAttachments<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, &'static Attachment, Option<&'static Model>),
+    Or<(Changed<Attachment>, Without<Attached>)>,
+>;
+
+/// Parent new or changed [`Attachment`]s to their joint's bone once the
+/// carrier's [`Joints`] are known
+pub fn bind_attachments(
+    mut commands: Commands,
+    registry: Res<ModelManifests>,
+    manifests: Res<Assets<ModelManifest>>,
+    carriers: Query<Option<&Joints>>,
+    attachments: PendingAttachments,
+) {
+    for (entity, attachment, model) in &attachments {
+        let Ok(joints) = carriers.get(attachment.carrier) else {
+            warn!("Carrier of {} is gone, dropping its attachment", entity);
+            commands.entity(entity).remove::<Attachment>();
+            continue;
+        };
+        let Some(joints) = joints else {
+            continue;
+        };
+        let Some((bone, offset)) = joints.get(&attachment.joint) else {
+            warn!(
+                "No joint '{}' on {} to attach {} to",
+                attachment.joint, attachment.carrier, entity
+            );
+            commands.entity(entity).remove::<Attachment>();
+            continue;
+        };
 
-pub fn get_assembly_transform<
-    Carrier: Localizable<CarrierJoint>,
-    CarrierJoint,
-    Carried: Localizable<CarriedJoint>,
-    CarriedJoint,
->() -> Transform {
-    let carrier_position = <Carrier as Localizable<CarrierJoint>>::position();
-    let carried_position = <Carried as Localizable<CarriedJoint>>::position();
-    let assembly = (carrier_position - carried_position) / 16.0;
-    Transform::from_xyz(assembly.x, assembly.y, assembly.z)
+        let carried = attachment
+            .carried_joint
+            .as_deref()
+            .and_then(|joint| {
+                let manifest = registry.get(&manifests, &model?.entity_type)?;
+                manifest.joints.get(joint)?.position()
+            })
+            .unwrap_or(Vec3::ZERO);
+        commands.entity(entity).insert((
+            ChildOf(bone),
+            offset
+                * Transform::from_translation(
+                    attachment.offset - carried / PIXELS_PER_BLOCK,
+                ),
+            Attached { bone },
+        ));
+    }
 }
@@ -0,0 +1,583 @@
+use std::{collections::HashMap, fmt, io};
+
+use bevy::{
+    animation::graph::AnimationGraph,
+    asset::{
+        io::Reader, Asset, AssetEvent, AssetId, AssetLoader, AssetServer,
+        Assets, Handle, LoadContext, LoadedFolder,
+    },
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::EventReader,
+        query::Without,
+        resource::Resource,
+        system::{Commands, Query, Res, ResMut, SystemParam},
+    },
+    gltf::{Gltf, GltfAssetLabel, GltfNode},
+    log::{error, warn},
+    platform::collections::HashSet,
+    reflect::TypePath,
+    scene::{Scene, SceneRoot},
+};
+use serde::Deserialize;
+
+use crate::{
+    animation::{
+        AnimationConfig, AnimationConfigs, ClipSource, ModelAnimation,
+    },
+    components::texture_override::TextureOverride,
+    entity::equipment::EquipmentSlot,
+    model::{
+        animation::AnimationAssets,
+        assembly::JointManifest,
+        mask::BoneMasks,
+        procedural::{HeadManifest, LimbManifest},
+        register_animations, BlendGraphConfig, ClipResolver,
+    },
+};
+
+/// Folder under `assets/` holding every model manifest
+pub const MANIFEST_FOLDER: &str = "models/manifests";
+
+/// Everything needed to spawn and animate a model, read from a
+/// `*.model.ron` or `*.model.json` file.
+///
+/// ```ron
+/// (
+///     entity_type: "zombie",
+///     model: "models/zombie.gltf",
+///     texture: Some("images/entity/{variant}.png"),
+///     variants: ["zombie"],
+///     riding_animation: Some("riding"),
+///     animations: {
+///         "walk": (clip: "animation.zombie.walk", repeat: true),
+///         "baby": (clip: "#Animation3", paused: true),
+///         "attack": (clip: "animation.zombie.attack", mask: Some("arms")),
+///     },
+///     blends: {
+///         "baby_walk": (animations: [("baby", 0.5), ("walk", 0.5)]),
+///         "walk_attack": (animations: [("walk", 1.0), ("attack", 1.0)]),
+///     },
+///     masks: {
+///         "arms": ["left_arm", "right_arm"],
+///     },
+///     armor: {
+///         head: ["head"],
+///         chest: ["body", "left_arm", "right_arm"],
+///     },
+///     joints: {
+///         "head": (bone: Some("head"), position: Some((-4.0, 24.0, -4.0))),
+///         "right_hand": (bone: Some("right_arm")),
+///     },
+///     head: Some((bone: "head", max_yaw: 70.0, max_pitch: 40.0)),
+///     limbs: [
+///         (bone: "left_leg", phase: 180.0),
+///         (bone: "right_leg"),
+///     ],
+/// )
+/// ```
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+pub struct ModelManifest {
+    pub entity_type: String,
+    /// glTF file, relative to the assets folder
+    pub model: String,
+    /// Relative to the assets folder, `{variant}` is replaced by the
+    /// variant name
+    #[serde(default)]
+    pub texture: Option<String>,
+    /// The first one is used when no variant is asked for
+    #[serde(default)]
+    pub variants: Vec<String>,
+    #[serde(default)]
+    pub default_animation: Option<String>,
+    /// Played while the model [`Rides`](crate::entity::riding::Rides)
+    /// something
+    #[serde(default)]
+    pub riding_animation: Option<String>,
+    #[serde(default)]
+    pub animations: HashMap<String, AnimationManifest>,
+    #[serde(default)]
+    pub blends: HashMap<String, BlendManifest>,
+    /// Bone masks by name, each a list of glTF bone names. A bone's children
+    /// belong to its mask unless they are listed themselves.
+    #[serde(default)]
+    pub masks: HashMap<String, Vec<String>>,
+    /// Attachment points by name, see
+    /// [`Attachment`](crate::model::assembly::Attachment)
+    #[serde(default)]
+    pub joints: HashMap<String, JointManifest>,
+    /// Bones covered by the armor of each slot, see
+    /// [`Equipment`](crate::entity::equipment::Equipment)
+    #[serde(default)]
+    pub armor: HashMap<EquipmentSlot, Vec<String>>,
+    /// Bone turned towards what the model looks at
+    #[serde(default)]
+    pub head: Option<HeadManifest>,
+    /// Bones swinging while the model walks
+    #[serde(default)]
+    pub limbs: Vec<LimbManifest>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AnimationManifest {
+    /// Name of the animation inside the glTF file, or a positional label
+    /// such as `#Animation2`
+    pub clip: String,
+    #[serde(default = "default_speed")]
+    pub speed: f32,
+    #[serde(default)]
+    pub repeat: bool,
+    #[serde(default)]
+    pub paused: bool,
+    /// Only drive the bones of one of the model's `masks`
+    #[serde(default)]
+    pub mask: Option<String>,
+}
+
+/// See [`BlendGraphConfig`]
+#[derive(Debug, Clone, Deserialize)]
+pub struct BlendManifest {
+    /// Animation names and their weight in the blend
+    pub animations: Vec<(String, f32)>,
+    #[serde(default = "default_speed")]
+    pub speed: f32,
+    #[serde(default)]
+    pub repeat: bool,
+    #[serde(default)]
+    pub paused: bool,
+}
+
+fn default_speed() -> f32 {
+    1.0
+}
+
+#[derive(Debug)]
+pub enum ModelManifestError {
+    Io(io::Error),
+    Ron(ron::error::SpannedError),
+    Json(serde_json::Error),
+    UnknownAnimation { blend: String, animation: String },
+    UnknownMask { animation: String, mask: String },
+}
+
+impl fmt::Display for ModelManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelManifestError::Io(err) => write!(f, "io error: {}", err),
+            ModelManifestError::Ron(err) => write!(f, "ron error: {}", err),
+            ModelManifestError::Json(err) => write!(f, "json error: {}", err),
+            ModelManifestError::UnknownAnimation { blend, animation } => {
+                write!(
+                    f,
+                    "blend '{}' uses unknown animation '{}'",
+                    blend, animation
+                )
+            }
+            ModelManifestError::UnknownMask { animation, mask } => {
+                write!(
+                    f,
+                    "animation '{}' uses unknown mask '{}'",
+                    animation, mask
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ModelManifestError {}
+
+impl From<io::Error> for ModelManifestError {
+    fn from(err: io::Error) -> Self {
+        ModelManifestError::Io(err)
+    }
+}
+
+impl ModelManifest {
+    /// Pick the format from the file extension, RON unless it is `.json`
+    pub fn parse(path: &str, text: &str) -> Result<Self, ModelManifestError> {
+        let manifest: Self = if path.ends_with(".json") {
+            serde_json::from_str(text).map_err(ModelManifestError::Json)?
+        } else {
+            ron::from_str(text).map_err(ModelManifestError::Ron)?
+        };
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    pub fn texture_path(&self, variant: Option<&str>) -> Option<String> {
+        let variant = variant
+            .or(self.variants.first().map(String::as_str))
+            .unwrap_or_default();
+        Some(self.texture.as_ref()?.replace("{variant}", variant))
+    }
+
+    pub fn scene(&self, asset_server: &AssetServer) -> Handle<Scene> {
+        asset_server
+            .load(GltfAssetLabel::Scene(0).from_asset(self.model.clone()))
+    }
+
+    /// `model@name` for names, `model#AnimationN` for labels, see
+    /// [`ClipSource`]
+    fn clip_path(&self, clip: &str) -> String {
+        if clip.starts_with('#') {
+            format!("{}{}", self.model, clip)
+        } else {
+            format!("{}@{}", self.model, clip)
+        }
+    }
+
+    /// Compare the animation names used by the manifest with the ones in
+    /// its glTF file
+    pub fn check_animations(&self, gltf: &Gltf) -> AnimationCheck {
+        let used: HashSet<&str> = self
+            .animations
+            .values()
+            .filter(|animation| !animation.clip.starts_with('#'))
+            .map(|animation| animation.clip.as_str())
+            .collect();
+
+        let mut missing: Vec<String> = used
+            .iter()
+            .filter(|name| !gltf.named_animations.contains_key(**name))
+            .map(|name| name.to_string())
+            .collect();
+        let mut extra: Vec<String> = gltf
+            .named_animations
+            .keys()
+            .filter(|name| !used.contains(name.as_ref()))
+            .map(|name| name.to_string())
+            .collect();
+        missing.sort();
+        extra.sort();
+
+        AnimationCheck { missing, extra }
+    }
+
+    pub fn animation_configs(&self) -> HashMap<String, AnimationConfig> {
+        self.animations
+            .iter()
+            .map(|(name, animation)| {
+                let config = AnimationConfig::Single {
+                    path: self.clip_path(&animation.clip),
+                    speed: animation.speed,
+                    repeat: animation.repeat,
+                    paused: animation.paused,
+                    mask: animation.mask.clone(),
+                };
+                (name.clone(), config)
+            })
+            .collect()
+    }
+
+    pub fn blend_graph_configs(&self) -> HashMap<String, BlendGraphConfig> {
+        self.blends
+            .iter()
+            .map(|(name, blend)| {
+                let config = BlendGraphConfig {
+                    animations: blend.animations.clone(),
+                    speed: blend.speed,
+                    repeat: blend.repeat,
+                    paused: blend.paused,
+                };
+                (name.clone(), config)
+            })
+            .collect()
+    }
+
+    /// Register the animations under this model's entity type, replacing
+    /// the configs of an earlier version of the manifest
+    pub fn register(
+        &self,
+        resolve: &ClipResolver,
+        animation_assets: &mut AnimationAssets,
+        animation_configs: &mut AnimationConfigs,
+    ) {
+        let configs = animation_configs.model_mut(&self.entity_type);
+        configs.clear();
+        register_animations(
+            self.animation_configs(),
+            self.blend_graph_configs(),
+            resolve,
+            animation_assets.model_mut(&self.entity_type),
+            configs,
+        );
+    }
+
+    fn validate(&self) -> Result<(), ModelManifestError> {
+        for (name, animation) in &self.animations {
+            if let Some(mask) = &animation.mask {
+                if !self.masks.contains_key(mask) {
+                    return Err(ModelManifestError::UnknownMask {
+                        animation: name.clone(),
+                        mask: mask.clone(),
+                    });
+                }
+            }
+        }
+        for (blend, config) in &self.blends {
+            for (animation, _) in &config.animations {
+                if !self.animations.contains_key(animation) {
+                    return Err(ModelManifestError::UnknownAnimation {
+                        blend: blend.clone(),
+                        animation: animation.clone(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Result of [`ModelManifest::check_animations`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnimationCheck {
+    /// Used by the manifest but not in the glTF file
+    pub missing: Vec<String>,
+    /// In the glTF file but not used by the manifest
+    pub extra: Vec<String>,
+}
+
+/// Loads `*.model.ron` and `*.model.json` files into a [`ModelManifest`]
+#[derive(Default)]
+pub struct ModelManifestLoader;
+
+impl AssetLoader for ModelManifestLoader {
+    type Asset = ModelManifest;
+    type Settings = ();
+    type Error = ModelManifestError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<ModelManifest, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let text = String::from_utf8_lossy(&bytes);
+
+        let path = load_context.path().to_string_lossy().to_string();
+        ModelManifest::parse(&path, &text)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["model.ron", "model.json"]
+    }
+}
+
+/// Every registered manifest by entity type. The folder handle keeps them
+/// loaded and lets them hot reload.
+#[derive(Resource, Default)]
+pub struct ModelManifests {
+    _folder: Handle<LoadedFolder>,
+    by_type: HashMap<String, AssetId<ModelManifest>>,
+    gltfs: HashMap<String, Handle<Gltf>>,
+    /// Manifests waiting for their glTF file to load
+    pending: Vec<(AssetId<ModelManifest>, Handle<Gltf>)>,
+}
+
+impl ModelManifests {
+    pub fn get<'a>(
+        &self,
+        manifests: &'a Assets<ModelManifest>,
+        entity_type: &str,
+    ) -> Option<&'a ModelManifest> {
+        manifests.get(*self.by_type.get(entity_type)?)
+    }
+
+    /// Register a manifest already in `Assets` without waiting for its glTF
+    /// file, leaving its animations unregistered
+    pub fn insert(
+        &mut self,
+        entity_type: &str,
+        manifest: impl Into<AssetId<ModelManifest>>,
+    ) {
+        self.by_type.insert(entity_type.to_string(), manifest.into());
+    }
+
+    /// The glTF file of a registered model
+    pub fn gltf(&self, entity_type: &str) -> Option<&Handle<Gltf>> {
+        self.gltfs.get(entity_type)
+    }
+
+    pub fn entity_types(&self) -> impl Iterator<Item = &str> {
+        self.by_type.keys().map(String::as_str)
+    }
+}
+
+/// A model spawned from its manifest. The scene and texture are added by
+/// [`spawn_models`] once the manifest is loaded.
+#[derive(Component, Clone, Debug)]
+pub struct Model {
+    pub entity_type: String,
+    pub variant: Option<String>,
+}
+
+impl Model {
+    pub fn new(entity_type: &str) -> Self {
+        Self {
+            entity_type: entity_type.to_string(),
+            variant: None,
+        }
+    }
+
+    pub fn with_variant(mut self, variant: &str) -> Self {
+        self.variant = Some(variant.to_string());
+        self
+    }
+}
+
+pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(ModelManifests {
+        _folder: asset_server.load_folder(MANIFEST_FOLDER),
+        by_type: HashMap::new(),
+        gltfs: HashMap::new(),
+        pending: Vec::new(),
+    });
+    commands.insert_resource(AnimationAssets::new());
+    commands.insert_resource(AnimationConfigs::default());
+}
+
+/// Where registered animations end up
+#[derive(SystemParam)]
+pub struct AnimationRegistry<'w> {
+    graphs: ResMut<'w, Assets<AnimationGraph>>,
+    animation_assets: ResMut<'w, AnimationAssets>,
+    animation_configs: ResMut<'w, AnimationConfigs>,
+}
+
+/// Register the animations of new and changed manifests once their glTF
+/// file is loaded, reporting animation names missing from the file and
+/// ones the manifest does not use
+pub fn register_model_manifests(
+    mut events: EventReader<AssetEvent<ModelManifest>>,
+    manifests: Res<Assets<ModelManifest>>,
+    gltfs: Res<Assets<Gltf>>,
+    gltf_nodes: Res<Assets<GltfNode>>,
+    asset_server: Res<AssetServer>,
+    mut registry: ResMut<ModelManifests>,
+    mut animations: AnimationRegistry,
+) {
+    for event in events.read() {
+        let (AssetEvent::Added { id } | AssetEvent::Modified { id }) = *event
+        else {
+            continue;
+        };
+        let Some(manifest) = manifests.get(id) else {
+            continue;
+        };
+        let gltf = asset_server.load(manifest.model.clone());
+        registry.pending.retain(|(pending, _)| *pending != id);
+        registry.pending.push((id, gltf));
+    }
+
+    let pending = std::mem::take(&mut registry.pending);
+    for (id, gltf_handle) in pending {
+        let Some(manifest) = manifests.get(id) else {
+            continue;
+        };
+        let Some(gltf) = gltfs.get(&gltf_handle) else {
+            if asset_server.load_state(&gltf_handle).is_failed() {
+                error!(
+                    "Model '{}' not registered, {} failed to load",
+                    manifest.entity_type, manifest.model
+                );
+            } else {
+                registry.pending.push((id, gltf_handle));
+            }
+            continue;
+        };
+
+        let check = manifest.check_animations(gltf);
+        if !check.missing.is_empty() {
+            error!(
+                "Model '{}': animations missing from {}: {:?}",
+                manifest.entity_type, manifest.model, check.missing
+            );
+        }
+        if !check.extra.is_empty() {
+            warn!(
+                "Model '{}': animations in {} not used: {:?}",
+                manifest.entity_type, manifest.model, check.extra
+            );
+        }
+
+        let masks = BoneMasks::from_gltf(gltf, &gltf_nodes, &manifest.masks);
+        if !masks.missing.is_empty() {
+            warn!(
+                "Model '{}': mask bones missing from {}: {:?}",
+                manifest.entity_type, manifest.model, masks.missing
+            );
+        }
+        animations
+            .animation_assets
+            .model_mut(&manifest.entity_type)
+            .set_masks(masks);
+
+        if let Some(previous) =
+            registry.by_type.insert(manifest.entity_type.clone(), id)
+        {
+            if previous != id {
+                error!(
+                    "Two manifests define the model '{}'",
+                    manifest.entity_type
+                );
+            }
+        }
+
+        let resolve = |path: &str| match ClipSource::parse(path) {
+            ClipSource::Label(path) => Some(asset_server.load(path)),
+            ClipSource::Named { name, .. } => {
+                gltf.named_animations.get(name).cloned()
+            }
+        };
+        manifest.register(
+            &resolve,
+            &mut animations.animation_assets,
+            &mut animations.animation_configs,
+        );
+        registry
+            .gltfs
+            .insert(manifest.entity_type.clone(), gltf_handle);
+    }
+
+    animations
+        .animation_assets
+        .finalize_graphs(&mut animations.graphs);
+}
+
+/// Give [`Model`]s their scene and texture, and the manifest's default
+/// animation if they have none
+pub fn spawn_models(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    registry: Res<ModelManifests>,
+    manifests: Res<Assets<ModelManifest>>,
+    models: Query<
+        (Entity, &Model, Option<&ModelAnimation>),
+        Without<SceneRoot>,
+    >,
+) {
+    for (entity, model, animation) in &models {
+        let Some(manifest) = registry.get(&manifests, &model.entity_type)
+        else {
+            continue;
+        };
+
+        let texture = manifest
+            .texture_path(model.variant.as_deref())
+            .map(|path| asset_server.load(path));
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.insert((
+            TextureOverride(texture),
+            SceneRoot(manifest.scene(&asset_server)),
+        ));
+
+        if let (None, Some(default_animation)) =
+            (animation, &manifest.default_animation)
+        {
+            entity_commands.insert(ModelAnimation::new(default_animation));
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use bevy::{
+    animation::{
+        graph::{AnimationGraph, AnimationMask},
+        AnimationTargetId,
+    },
+    asset::{Assets, Handle},
+    ecs::name::Name,
+    gltf::{Gltf, GltfNode},
+    platform::collections::HashSet,
+};
+
+/// Bones of a model sorted into animation mask groups: one group per named
+/// mask, plus one for every bone no mask claims.
+///
+/// A bone belongs to the mask listing it or, failing that, to the mask of
+/// its nearest listed ancestor, so listing `left_arm` also claims the hand
+/// below it.
+#[derive(Debug, Clone, Default)]
+pub struct BoneMasks {
+    /// Mask names, the index is the mask group
+    names: Vec<String>,
+    targets: Vec<(AnimationTargetId, u32)>,
+    /// Bones listed by a mask but not found in the model
+    pub missing: Vec<String>,
+}
+
+impl BoneMasks {
+    /// `masks` maps mask names to glTF bone names
+    pub fn from_gltf(
+        gltf: &Gltf,
+        nodes: &Assets<GltfNode>,
+        masks: &HashMap<String, Vec<String>>,
+    ) -> Self {
+        Self::from_paths(node_paths(gltf, nodes), masks)
+    }
+
+    /// Build the groups from the name path of every node, starting at the
+    /// scene's root node like the glTF loader's animation targets
+    pub fn from_paths(
+        paths: impl IntoIterator<Item = Vec<String>>,
+        masks: &HashMap<String, Vec<String>>,
+    ) -> Self {
+        let mut names: Vec<String> = masks.keys().cloned().collect();
+        names.sort();
+        // Only 64 groups fit in an `AnimationMask`, one is kept for the rest
+        names.truncate(AnimationMask::BITS as usize - 1);
+
+        // A bone listed twice goes to the first mask by name
+        let mut bone_groups: HashMap<&str, u32> = HashMap::new();
+        for (group, name) in names.iter().enumerate().rev() {
+            for bone in &masks[name] {
+                bone_groups.insert(bone, group as u32);
+            }
+        }
+
+        let rest = names.len() as u32;
+        let mut found = HashSet::new();
+        let targets = paths
+            .into_iter()
+            .map(|path| {
+                let group = path
+                    .iter()
+                    .rev()
+                    .find_map(|name| bone_groups.get(name.as_str()).copied())
+                    .unwrap_or(rest);
+                if let Some(name) = path.last() {
+                    found.insert(name.clone());
+                }
+                let path: Vec<Name> = path.into_iter().map(Name::new).collect();
+                (AnimationTargetId::from_names(path.iter()), group)
+            })
+            .collect();
+
+        let mut missing: Vec<String> = bone_groups
+            .keys()
+            .filter(|bone| !found.contains(**bone))
+            .map(|bone| bone.to_string())
+            .collect();
+        missing.sort();
+
+        Self {
+            names,
+            targets,
+            missing,
+        }
+    }
+
+    /// Group of a mask, for [`AnimationGraph::add_target_to_mask_group`]
+    pub fn group(&self, mask: &str) -> Option<u32> {
+        self.names
+            .iter()
+            .position(|name| name == mask)
+            .map(|i| i as u32)
+    }
+
+    /// Graph node mask for a clip that only drives the bones of `mask`.
+    /// `None` or an unknown mask drives every bone.
+    pub fn only(&self, mask: Option<&str>) -> AnimationMask {
+        let Some(group) = mask.and_then(|mask| self.group(mask)) else {
+            return 0;
+        };
+        // Every group, the rest included
+        let all = AnimationMask::MAX >> (63 - self.names.len());
+        all & !(1 << group)
+    }
+
+    pub fn apply(&self, graph: &mut AnimationGraph) {
+        for &(target, group) in &self.targets {
+            graph.add_target_to_mask_group(target, group);
+        }
+    }
+}
+
+/// Name path from the scene's root node to every node of the glTF file
+fn node_paths(gltf: &Gltf, nodes: &Assets<GltfNode>) -> Vec<Vec<String>> {
+    let children: HashSet<_> = gltf
+        .nodes
+        .iter()
+        .filter_map(|node| nodes.get(node))
+        .flat_map(|node| node.children.iter().map(Handle::id))
+        .collect();
+
+    let mut stack: Vec<(Handle<GltfNode>, Vec<String>)> = gltf
+        .nodes
+        .iter()
+        .filter(|node| !children.contains(&node.id()))
+        .map(|node| (node.clone(), Vec::new()))
+        .collect();
+
+    let mut paths = Vec::new();
+    while let Some((handle, mut path)) = stack.pop() {
+        let Some(node) = nodes.get(&handle) else {
+            continue;
+        };
+        path.push(node.name.clone());
+        for child in &node.children {
+            stack.push((child.clone(), path.clone()));
+        }
+        paths.push(path);
+    }
+    paths
+}
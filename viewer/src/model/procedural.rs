@@ -0,0 +1,316 @@
+use std::f32::consts::PI;
+
+use bevy::{
+    asset::Assets,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        hierarchy::ChildOf,
+        observer::Trigger,
+        query::{With, Without},
+        system::{Commands, Query, Res},
+    },
+    math::{Quat, Vec3, Vec3Swizzles},
+    scene::SceneInstanceReady,
+    time::Time,
+    transform::components::{GlobalTransform, Transform},
+};
+use serde::Deserialize;
+
+use crate::{
+    model::{
+        assembly::Bones,
+        manifest::{Model, ModelManifest, ModelManifests},
+    },
+    simple_control::PlayerCamera,
+};
+
+/// How far a model notices what it looks at, in blocks
+const LOOK_RANGE: f32 = 8.0;
+/// Fraction of the remaining head turn done per second
+const HEAD_TURN_RATE: f32 = 8.0;
+/// Swing phase in radians per block walked
+const SWING_PER_BLOCK: f32 = 4.0;
+/// Blocks per second at which limbs swing fully
+const FULL_SWING_SPEED: f32 = 4.0;
+/// Fraction of the remaining swing amount change done per second
+const SWING_RATE: f32 = 10.0;
+
+/// Head bone of a model manifest, angles in degrees
+#[derive(Debug, Clone, Deserialize)]
+pub struct HeadManifest {
+    pub bone: String,
+    #[serde(default = "default_max_yaw")]
+    pub max_yaw: f32,
+    #[serde(default = "default_max_pitch")]
+    pub max_pitch: f32,
+}
+
+/// A bone swinging back and forth while the model walks, angles in degrees
+#[derive(Debug, Clone, Deserialize)]
+pub struct LimbManifest {
+    pub bone: String,
+    /// Offset in the swing cycle, 180 for the opposite leg
+    #[serde(default)]
+    pub phase: f32,
+    #[serde(default = "default_amplitude")]
+    pub amplitude: f32,
+}
+
+fn default_max_yaw() -> f32 {
+    70.0
+}
+
+fn default_max_pitch() -> f32 {
+    40.0
+}
+
+fn default_amplitude() -> f32 {
+    80.0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LookTarget {
+    /// The player's camera
+    Camera,
+    Entity(Entity),
+    /// Keep the head straight
+    None,
+}
+
+/// What a model turns its head towards. Models whose manifest has a head
+/// get one looking at the camera unless they already have one.
+#[derive(Component, Debug, Clone)]
+pub struct HeadLook {
+    pub target: LookTarget,
+    /// Targets further away than this are ignored
+    pub range: f32,
+}
+
+impl Default for HeadLook {
+    fn default() -> Self {
+        Self {
+            target: LookTarget::Camera,
+            range: LOOK_RANGE,
+        }
+    }
+}
+
+/// Movement of a model driving its [`LimbBone`]s
+#[derive(Component, Debug, Clone, Default)]
+pub struct LimbSwing {
+    /// Blocks walked, the swing cycle position
+    pub distance: f32,
+    /// How far the limbs swing, from 0 standing to 1 at full speed
+    pub amount: f32,
+    last_position: Option<Vec3>,
+}
+
+/// Rotation a controller added on top of the animation last frame, undone
+/// by [`reset_procedural_bones`] before the next animation pass
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct ProceduralOffset(Quat);
+
+#[derive(Component, Debug, Clone)]
+pub struct HeadBone {
+    pub model: Entity,
+    /// Radians
+    pub max_yaw: f32,
+    pub max_pitch: f32,
+    /// Current angles in radians, eased towards the target
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+#[derive(Component, Debug, Clone)]
+pub struct LimbBone {
+    pub model: Entity,
+    /// Radians
+    pub phase: f32,
+    pub amplitude: f32,
+}
+
+impl LimbBone {
+    /// Rotation around the bone's X axis, Minecraft style
+    pub fn angle(&self, swing: &LimbSwing) -> f32 {
+        (swing.distance * SWING_PER_BLOCK + self.phase).cos()
+            * self.amplitude
+            * swing.amount
+    }
+}
+
+/// Yaw and pitch in radians turning a model's forward, -Z, towards a
+/// direction given in the same space
+pub fn look_angles(direction: Vec3) -> (f32, f32) {
+    let yaw = (-direction.x).atan2(-direction.z);
+    let pitch = direction.y.atan2(direction.xz().length());
+    (yaw, pitch)
+}
+
+/// Find the manifest's head and limb bones in a freshly spawned scene
+pub fn setup_bone_controllers(
+    trigger: Trigger<SceneInstanceReady>,
+    mut commands: Commands,
+    registry: Res<ModelManifests>,
+    manifests: Res<Assets<ModelManifest>>,
+    models: Query<(&Model, Option<&HeadLook>)>,
+    bones: Bones,
+) {
+    let model = trigger.target();
+    let Ok((model_info, head_look)) = models.get(model) else {
+        return;
+    };
+    let Some(manifest) = registry.get(&manifests, &model_info.entity_type)
+    else {
+        return;
+    };
+
+    let find_bone = |bone: &str| bones.find(model, bone);
+
+    if let Some(head) = &manifest.head {
+        if let Some(bone) = find_bone(&head.bone) {
+            commands.entity(bone).insert((
+                HeadBone {
+                    model,
+                    max_yaw: head.max_yaw.to_radians(),
+                    max_pitch: head.max_pitch.to_radians(),
+                    yaw: 0.0,
+                    pitch: 0.0,
+                },
+                ProceduralOffset::default(),
+            ));
+            if head_look.is_none() {
+                commands.entity(model).insert(HeadLook::default());
+            }
+        }
+    }
+
+    let mut has_limbs = false;
+    for limb in &manifest.limbs {
+        let Some(bone) = find_bone(&limb.bone) else {
+            continue;
+        };
+        commands.entity(bone).insert((
+            LimbBone {
+                model,
+                phase: limb.phase.to_radians(),
+                amplitude: limb.amplitude.to_radians(),
+            },
+            ProceduralOffset::default(),
+        ));
+        has_limbs = true;
+    }
+    if has_limbs {
+        commands.entity(model).insert(LimbSwing::default());
+    }
+}
+
+/// Follow how fast each model moves horizontally
+pub fn track_limb_swing(
+    time: Res<Time>,
+    mut models: Query<(&GlobalTransform, &mut LimbSwing)>,
+) {
+    let dt = time.delta_secs();
+    if dt <= 0.0 {
+        return;
+    }
+    for (transform, mut swing) in &mut models {
+        let position = transform.translation();
+        let moved = swing
+            .last_position
+            .map_or(0.0, |last| (position - last).xz().length());
+        swing.last_position = Some(position);
+
+        let target = (moved / dt / FULL_SWING_SPEED).min(1.0);
+        let amount = swing.amount;
+        swing.amount += (target - amount) * (SWING_RATE * dt).min(1.0);
+        swing.distance += moved;
+    }
+}
+
+/// Ease head bones towards their model's [`HeadLook`] target, within the
+/// bone's limits
+pub fn update_head_look(
+    time: Res<Time>,
+    models: Query<&HeadLook>,
+    camera: Query<&GlobalTransform, With<PlayerCamera>>,
+    transforms: Query<&GlobalTransform, Without<HeadBone>>,
+    mut heads: Query<(&mut HeadBone, &GlobalTransform, &ChildOf)>,
+) {
+    let ease = (HEAD_TURN_RATE * time.delta_secs()).min(1.0);
+
+    for (mut head, head_transform, child_of) in &mut heads {
+        let target = models.get(head.model).ok().and_then(|look| {
+            let target = match look.target {
+                LookTarget::Camera => camera.single().ok(),
+                LookTarget::Entity(entity) => transforms.get(entity).ok(),
+                LookTarget::None => None,
+            }?
+            .translation();
+            let head_position = head_transform.translation();
+            (target.distance(head_position) <= look.range)
+                .then_some(target - head_position)
+        });
+        let Ok(parent) = transforms.get(child_of.parent()) else {
+            continue;
+        };
+
+        let (yaw, pitch) = target
+            .map(|direction| {
+                let local =
+                    parent.affine().inverse().transform_vector3(direction);
+                look_angles(local)
+            })
+            // Turn back once the target is behind the limits
+            .filter(|(yaw, _)| yaw.abs() <= head.max_yaw + PI / 4.0)
+            .map_or((0.0, 0.0), |(yaw, pitch)| {
+                (
+                    yaw.clamp(-head.max_yaw, head.max_yaw),
+                    pitch.clamp(-head.max_pitch, head.max_pitch),
+                )
+            });
+
+        head.yaw += (yaw - head.yaw) * ease;
+        head.pitch += (pitch - head.pitch) * ease;
+    }
+}
+
+/// Undo last frame's controller rotations. Bones the animation does not
+/// drive would keep them otherwise.
+pub fn reset_procedural_bones(
+    mut bones: Query<(&mut Transform, &mut ProceduralOffset)>,
+) {
+    for (mut transform, mut offset) in &mut bones {
+        transform.rotation = offset.0.inverse() * transform.rotation;
+        offset.0 = Quat::IDENTITY;
+    }
+}
+
+/// Rotate head and limb bones on top of the pose the animation left, runs
+/// between the animation pass and transform propagation. The rotation is
+/// applied in the parent's space, so a tilted bone still turns around the
+/// model's vertical axis.
+pub fn apply_procedural_bones(
+    swings: Query<&LimbSwing>,
+    mut bones: Query<(
+        &mut Transform,
+        &mut ProceduralOffset,
+        Option<&HeadBone>,
+        Option<&LimbBone>,
+    )>,
+) {
+    for (mut transform, mut offset, head, limb) in &mut bones {
+        let mut rotation = Quat::IDENTITY;
+        if let Some(head) = head {
+            rotation *= Quat::from_rotation_y(head.yaw)
+                * Quat::from_rotation_x(head.pitch);
+        }
+        if let Some(limb) = limb {
+            if let Ok(swing) = swings.get(limb.model) {
+                rotation *= Quat::from_rotation_x(limb.angle(swing));
+            }
+        }
+        transform.rotation = rotation * transform.rotation;
+        offset.0 = rotation;
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use bevy::{
+    asset::{AssetId, Assets, Handle},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::EventReader,
+        resource::Resource,
+        system::{Commands, Query, Res, ResMut},
+    },
+    math::{primitives::Cuboid, Vec2, Vec3},
+    pbr::{MeshMaterial3d, StandardMaterial},
+    render::mesh::{Mesh, Mesh3d, VertexAttributeValues},
+    time::{Time, Timer, TimerMode},
+    transform::components::Transform,
+    utils::default,
+};
+use bevy_image::Image;
+use rand::Rng;
+
+use crate::{
+    block::BuiltBlockID,
+    built_block_mesh::get_texture_option,
+    chunk::{
+        BlockChangeCause, BlockChanged, BlockTextures, WorldPos, WorldReader,
+    },
+};
+
+/// Particles per axis spawned when a block breaks
+const PARTICLES_PER_AXIS: i32 = 3;
+const PARTICLE_SIZE: f32 = 0.1;
+/// Each particle shows this fraction of the block texture's width
+const UV_SCALE: f32 = 0.25;
+const GRAVITY: f32 = 12.0;
+
+#[derive(Component)]
+pub struct BlockParticle {
+    pub velocity: Vec3,
+    pub lifetime: Timer,
+}
+
+/// One cube per window of the texture a particle can show, so particles
+/// of a block share its material and still look different
+#[derive(Resource)]
+pub struct ParticleMeshes(pub Vec<Handle<Mesh>>);
+
+/// Particle material of each block texture used so far
+#[derive(Resource, Default)]
+pub struct ParticleMaterials(
+    pub HashMap<AssetId<Image>, Handle<StandardMaterial>>,
+);
+
+pub fn setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
+    let windows = (1.0 / UV_SCALE) as u32;
+    let variants = (0..windows)
+        .flat_map(|u| (0..windows).map(move |v| (u, v)))
+        .map(|(u, v)| {
+            let offset = Vec2::new(u as f32, v as f32) * UV_SCALE;
+            meshes.add(particle_mesh(offset))
+        })
+        .collect();
+    commands.insert_resource(ParticleMeshes(variants));
+    commands.init_resource::<ParticleMaterials>();
+}
+
+/// Cube showing the window of the texture starting at `uv_offset`
+fn particle_mesh(uv_offset: Vec2) -> Mesh {
+    let mut mesh = Mesh::from(Cuboid::from_length(PARTICLE_SIZE));
+    if let Some(VertexAttributeValues::Float32x2(uvs)) =
+        mesh.attribute_mut(Mesh::ATTRIBUTE_UV_0)
+    {
+        for uv in uvs {
+            *uv = (Vec2::from(*uv) * UV_SCALE + uv_offset).to_array();
+        }
+    }
+    mesh
+}
+
+/// Burst of small textured cubes for every block broken by the player
+pub fn spawn_block_break_particles(
+    mut commands: Commands,
+    mut block_changed_events: EventReader<BlockChanged>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut particle_materials: ResMut<ParticleMaterials>,
+    meshes: Res<ParticleMeshes>,
+    block_textures: Res<BlockTextures>,
+) {
+    let mut rng = rand::thread_rng();
+
+    for event in block_changed_events.read() {
+        if event.cause != BlockChangeCause::PlayerBreak {
+            continue;
+        }
+        let Some(texture) = block_texture(&block_textures, event.old) else {
+            continue;
+        };
+        let material = particle_materials
+            .0
+            .entry(texture.id())
+            .or_insert_with(|| {
+                materials.add(StandardMaterial {
+                    base_color_texture: Some(texture),
+                    perceptual_roughness: 1.0,
+                    ..default()
+                })
+            })
+            .clone();
+
+        let center = Vec3::new(
+            event.pos.x as f32,
+            event.pos.y as f32,
+            event.pos.z as f32,
+        );
+        let step = 1.0 / PARTICLES_PER_AXIS as f32;
+
+        for x in 0..PARTICLES_PER_AXIS {
+            for y in 0..PARTICLES_PER_AXIS {
+                for z in 0..PARTICLES_PER_AXIS {
+                    let offset =
+                        (Vec3::new(x as f32, y as f32, z as f32) + 0.5) * step
+                            - 0.5;
+                    let mesh = &meshes.0[rng.gen_range(0..meshes.0.len())];
+
+                    let velocity = offset * 4.0
+                        + Vec3::new(
+                            rng.gen_range(-0.5..0.5),
+                            rng.gen_range(1.0..2.5),
+                            rng.gen_range(-0.5..0.5),
+                        );
+
+                    commands.spawn((
+                        Mesh3d(mesh.clone()),
+                        MeshMaterial3d(material.clone()),
+                        Transform::from_translation(center + offset),
+                        BlockParticle {
+                            velocity,
+                            lifetime: Timer::from_seconds(
+                                rng.gen_range(0.4..1.0),
+                                TimerMode::Once,
+                            ),
+                        },
+                    ));
+                }
+            }
+        }
+    }
+}
+
+pub fn update_block_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    reader: WorldReader,
+    mut particles: Query<(Entity, &mut BlockParticle, &mut Transform)>,
+) {
+    let dt = time.delta_secs();
+
+    for (entity, mut particle, mut transform) in &mut particles {
+        if particle.lifetime.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        particle.velocity.y -= GRAVITY * dt;
+        let next = transform.translation + particle.velocity * dt;
+
+        let bottom = next - Vec3::Y * PARTICLE_SIZE / 2.0;
+        let inside_block = reader
+            .get_block(WorldPos {
+                x: bottom.x.round() as i32,
+                y: bottom.y.round() as i32,
+                z: bottom.z.round() as i32,
+            })
+            .block()
+            .is_some_and(|block| block != BuiltBlockID::Air);
+
+        if inside_block {
+            particle.velocity = Vec3::ZERO;
+        } else {
+            transform.translation = next;
+        }
+    }
+}
+
+fn block_texture(
+    block_textures: &BlockTextures,
+    block: BuiltBlockID,
+) -> Option<Handle<Image>> {
+    let index = get_texture_option(block.texture()?)?;
+    block_textures.0.get(index as usize).cloned()
+}
@@ -1,4 +1,5 @@
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
 pub struct Region {
     pub x: u32,
     pub y: u32,
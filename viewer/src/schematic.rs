@@ -0,0 +1,381 @@
+pub mod nbt;
+pub mod sponge;
+
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use bevy::math::UVec3;
+
+use crate::{
+    block::BuiltBlockID,
+    chunk::{
+        BlockChangeCause, RegionTooLarge, SetBlockEvent, WorldPos, WorldReader,
+    },
+};
+
+const MAGIC: &[u8; 4] = b"VSCH";
+const FORMAT_VERSION: u8 = 1;
+
+/// Most blocks a schematic file may hold, 256 × 256 × 256. Sizes come from
+/// the file header, so larger ones are refused before anything is allocated.
+pub const MAX_VOLUME: u64 = 1 << 24;
+
+/// A box of blocks cut out of a [`World`].
+///
+/// Blocks are stored as indices into `palette`, ordered
+/// `x + z * width + y * width * length` (the same order as chunks and the
+/// Sponge format).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schematic {
+    pub size: UVec3,
+    pub palette: Vec<BuiltBlockID>,
+    pub blocks: Vec<u16>,
+}
+
+#[derive(Debug)]
+pub enum SchematicError {
+    Io(io::Error),
+    BadMagic,
+    UnsupportedVersion(u32),
+    UnknownBlock(String),
+    Malformed(&'static str),
+}
+
+impl fmt::Display for SchematicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchematicError::Io(err) => write!(f, "io error: {}", err),
+            SchematicError::BadMagic => write!(f, "not a schematic file"),
+            SchematicError::UnsupportedVersion(version) => {
+                write!(f, "unsupported schematic version {}", version)
+            }
+            SchematicError::UnknownBlock(name) => {
+                write!(f, "unknown block '{}'", name)
+            }
+            SchematicError::Malformed(reason) => {
+                write!(f, "malformed schematic: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SchematicError {}
+
+impl From<io::Error> for SchematicError {
+    fn from(err: io::Error) -> Self {
+        SchematicError::Io(err)
+    }
+}
+
+/// Clockwise rotation around the Y axis, seen from above.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rotation {
+    #[default]
+    None,
+    Clockwise90,
+    Clockwise180,
+    Clockwise270,
+}
+
+/// Mirroring applied before rotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mirror {
+    #[default]
+    None,
+    /// Flip along the X axis (left <-> right)
+    X,
+    /// Flip along the Z axis (front <-> back)
+    Z,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Placement {
+    pub rotation: Rotation,
+    pub mirror: Mirror,
+    /// Keep existing blocks where the schematic contains air
+    pub skip_air: bool,
+}
+
+impl Placement {
+    /// Size of the pasted box after rotation
+    pub fn rotated_size(&self, size: UVec3) -> UVec3 {
+        match self.rotation {
+            Rotation::None | Rotation::Clockwise180 => size,
+            Rotation::Clockwise90 | Rotation::Clockwise270 => {
+                UVec3::new(size.z, size.y, size.x)
+            }
+        }
+    }
+
+    /// Map a local position inside a box of `size` to its offset after
+    /// mirroring and rotation. The result stays inside `rotated_size`.
+    pub fn transform(&self, local: UVec3, size: UVec3) -> UVec3 {
+        let (w, l) = (size.x - 1, size.z - 1);
+        let (x, z) = match self.mirror {
+            Mirror::None => (local.x, local.z),
+            Mirror::X => (w - local.x, local.z),
+            Mirror::Z => (local.x, l - local.z),
+        };
+        let (x, z) = match self.rotation {
+            Rotation::None => (x, z),
+            Rotation::Clockwise90 => (l - z, x),
+            Rotation::Clockwise180 => (w - x, l - z),
+            Rotation::Clockwise270 => (z, w - x),
+        };
+        UVec3::new(x, local.y, z)
+    }
+}
+
+impl Schematic {
+    /// Copy every block between `min` and `max` (both inclusive).
+    /// Blocks in unloaded chunks are captured as air.
+    pub fn capture(
+        reader: &WorldReader,
+        min: WorldPos,
+        max: WorldPos,
+    ) -> Result<Self, RegionTooLarge> {
+        let region = reader.get_region(min, max)?;
+
+        // Both are in storage order
+        let mut palette = Vec::new();
+        let blocks = region
+            .blocks
+            .into_iter()
+            .map(|lookup| {
+                let block = lookup.block().unwrap_or(BuiltBlockID::Air);
+                palette_index(&mut palette, block)
+            })
+            .collect();
+
+        Ok(Self {
+            size: region.size,
+            palette,
+            blocks,
+        })
+    }
+
+    pub fn index(&self, x: u32, y: u32, z: u32) -> usize {
+        (x + z * self.size.x + y * self.size.x * self.size.z) as usize
+    }
+
+    pub fn get(&self, x: u32, y: u32, z: u32) -> BuiltBlockID {
+        self.palette[self.blocks[self.index(x, y, z)] as usize]
+    }
+
+    /// Iterate over `(local position, block)` in storage order
+    pub fn iter(&self) -> impl Iterator<Item = (UVec3, BuiltBlockID)> + '_ {
+        let (w, l) = (self.size.x, self.size.z);
+        self.blocks
+            .iter()
+            .enumerate()
+            .map(move |(i, &palette_index)| {
+                let i = i as u32;
+                let local = UVec3::new(i % w, i / (w * l), (i / w) % l);
+                (local, self.palette[palette_index as usize])
+            })
+    }
+
+    /// Events that paste this schematic with its minimum corner at `origin`.
+    /// Send them through `EventWriter<SetBlockEvent>::write_batch`; missing
+    /// chunks are created by `handle_set_block_events`, which fills them in
+    /// once they are spawned.
+    pub fn set_block_events(
+        &self,
+        origin: WorldPos,
+        placement: Placement,
+    ) -> impl Iterator<Item = SetBlockEvent> + '_ {
+        self.iter()
+            .filter(move |(_, block)| {
+                !(placement.skip_air && *block == BuiltBlockID::Air)
+            })
+            .map(move |(local, block)| {
+                let offset = placement.transform(local, self.size);
+                SetBlockEvent {
+                    world_pos: WorldPos {
+                        x: origin.x + offset.x as i32,
+                        y: origin.y + offset.y as i32,
+                        z: origin.z + offset.z as i32,
+                    },
+                    block,
+                    cause: BlockChangeCause::Schematic,
+                }
+            })
+    }
+
+    /// Write the native format:
+    /// magic, version, size, palette names and bit-packed block indices.
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[FORMAT_VERSION])?;
+        for axis in self.size.to_array() {
+            writer.write_all(&axis.to_le_bytes())?;
+        }
+
+        writer.write_all(&(self.palette.len() as u16).to_le_bytes())?;
+        for block in &self.palette {
+            let name = block.name().as_bytes();
+            writer.write_all(&[name.len() as u8])?;
+            writer.write_all(name)?;
+        }
+
+        let bits = bits_per_block(self.palette.len());
+        writer.write_all(&[bits])?;
+        for word in pack(&self.blocks, bits) {
+            writer.write_all(&word.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read_from(mut reader: impl Read) -> Result<Self, SchematicError> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(SchematicError::BadMagic);
+        }
+
+        let version = read_u8(&mut reader)?;
+        if version != FORMAT_VERSION {
+            return Err(SchematicError::UnsupportedVersion(version as u32));
+        }
+
+        let size = UVec3::new(
+            read_u32(&mut reader)?,
+            read_u32(&mut reader)?,
+            read_u32(&mut reader)?,
+        );
+
+        let palette_len = u16::from_le_bytes(read_array(&mut reader)?);
+        let mut palette = Vec::with_capacity(palette_len as usize);
+        for _ in 0..palette_len {
+            let mut name = vec![0; read_u8(&mut reader)? as usize];
+            reader.read_exact(&mut name)?;
+            let name = String::from_utf8_lossy(&name);
+            let block = BuiltBlockID::from_name(&name)
+                .ok_or_else(|| SchematicError::UnknownBlock(name.into()))?;
+            palette.push(block);
+        }
+
+        let bits = read_u8(&mut reader)?;
+        if bits == 0 || bits > 16 {
+            return Err(SchematicError::Malformed("bits per block"));
+        }
+
+        let count = volume(size)
+            .filter(|&count| count <= MAX_VOLUME)
+            .ok_or(SchematicError::Malformed("size"))?
+            as usize;
+        // Grown as words arrive, so a truncated file fails before the whole
+        // buffer is allocated
+        let mut words = Vec::new();
+        for _ in 0..packed_len(count, bits) {
+            words.push(u64::from_le_bytes(read_array(&mut reader)?));
+        }
+
+        let blocks = unpack(&words, bits, count);
+        if blocks.iter().any(|&i| i as usize >= palette.len()) {
+            return Err(SchematicError::Malformed(
+                "palette index out of range",
+            ));
+        }
+
+        Ok(Self {
+            size,
+            palette,
+            blocks,
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Load a schematic, choosing the Sponge reader for `.schem` files
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SchematicError> {
+        let path = path.as_ref();
+        let reader = BufReader::new(File::open(path)?);
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("schem") => sponge::read(reader),
+            _ => Self::read_from(reader),
+        }
+    }
+}
+
+fn volume(size: UVec3) -> Option<u64> {
+    u64::from(size.x)
+        .checked_mul(u64::from(size.y))?
+        .checked_mul(u64::from(size.z))
+}
+
+pub(crate) fn palette_index(
+    palette: &mut Vec<BuiltBlockID>,
+    block: BuiltBlockID,
+) -> u16 {
+    match palette.iter().position(|&b| b == block) {
+        Some(index) => index as u16,
+        None => {
+            palette.push(block);
+            (palette.len() - 1) as u16
+        }
+    }
+}
+
+fn bits_per_block(palette_len: usize) -> u8 {
+    let max_index = palette_len.saturating_sub(1).max(1);
+    (usize::BITS - max_index.leading_zeros()) as u8
+}
+
+fn packed_len(count: usize, bits: u8) -> usize {
+    (count * bits as usize).div_ceil(64)
+}
+
+/// Pack indices tightly, allowing entries to straddle two words
+fn pack(indices: &[u16], bits: u8) -> Vec<u64> {
+    let bits = bits as usize;
+    let mut words = vec![0u64; packed_len(indices.len(), bits as u8)];
+    for (i, &index) in indices.iter().enumerate() {
+        let bit = i * bits;
+        let (word, offset) = (bit / 64, bit % 64);
+        words[word] |= (index as u64) << offset;
+        if offset + bits > 64 {
+            words[word + 1] |= (index as u64) >> (64 - offset);
+        }
+    }
+    words
+}
+
+fn unpack(words: &[u64], bits: u8, count: usize) -> Vec<u16> {
+    let bits = bits as usize;
+    let mask = (1u64 << bits) - 1;
+    (0..count)
+        .map(|i| {
+            let bit = i * bits;
+            let (word, offset) = (bit / 64, bit % 64);
+            let mut value = words[word] >> offset;
+            if offset + bits > 64 {
+                value |= words[word + 1] << (64 - offset);
+            }
+            (value & mask) as u16
+        })
+        .collect()
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut buf = [0; N];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn read_u8(reader: &mut impl Read) -> io::Result<u8> {
+    Ok(read_array::<1>(reader)?[0])
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    Ok(u32::from_le_bytes(read_array(reader)?))
+}
@@ -0,0 +1,184 @@
+//! Minimal reader for Minecraft's Named Binary Tag format (big-endian,
+//! optionally gzip compressed). Only reading is supported.
+
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Read},
+};
+
+use flate2::bufread::GzDecoder;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    End,
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    List(Vec<Tag>),
+    Compound(HashMap<String, Tag>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(map) => map.get(name),
+            _ => None,
+        }
+    }
+
+    /// Any integer tag widened to `i64`
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Tag::Byte(v) => Some(v as i64),
+            Tag::Short(v) => Some(v as i64),
+            Tag::Int(v) => Some(v as i64),
+            Tag::Long(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_compound(&self) -> Option<&HashMap<String, Tag>> {
+        match self {
+            Tag::Compound(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    pub fn as_byte_array(&self) -> Option<&[i8]> {
+        match self {
+            Tag::ByteArray(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+}
+
+/// Read the root tag, transparently un-gzipping the input.
+/// Returns the root name together with its tag.
+pub fn read(mut reader: impl BufRead) -> io::Result<(String, Tag)> {
+    let gzipped = reader.fill_buf()?.starts_with(&[0x1f, 0x8b]);
+    if gzipped {
+        read_root(&mut GzDecoder::new(reader))
+    } else {
+        read_root(&mut reader)
+    }
+}
+
+fn read_root(reader: &mut impl Read) -> io::Result<(String, Tag)> {
+    let id = read_i8(reader)?;
+    if id == 0 {
+        return Ok((String::new(), Tag::End));
+    }
+    let name = read_string(reader)?;
+    let tag = read_payload(reader, id)?;
+    Ok((name, tag))
+}
+
+/// Lengths come from the file, so buffers grow as data arrives instead of
+/// being allocated up front
+fn read_payload(reader: &mut impl Read, id: i8) -> io::Result<Tag> {
+    Ok(match id {
+        0 => Tag::End,
+        1 => Tag::Byte(read_i8(reader)?),
+        2 => Tag::Short(i16::from_be_bytes(read_array(reader)?)),
+        3 => Tag::Int(read_i32(reader)?),
+        4 => Tag::Long(i64::from_be_bytes(read_array(reader)?)),
+        5 => Tag::Float(f32::from_be_bytes(read_array(reader)?)),
+        6 => Tag::Double(f64::from_be_bytes(read_array(reader)?)),
+        7 => {
+            let len = read_len(reader)?;
+            let mut bytes = Vec::new();
+            reader.take(len as u64).read_to_end(&mut bytes)?;
+            if bytes.len() < len {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            Tag::ByteArray(bytes.into_iter().map(|b| b as i8).collect())
+        }
+        8 => Tag::String(read_string(reader)?),
+        9 => {
+            let element_id = read_i8(reader)?;
+            let len = read_len(reader)?;
+            // End tags take no bytes, a long list of them would read nothing
+            if element_id == 0 && len > 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "nbt list of end tags",
+                ));
+            }
+            let mut list = Vec::new();
+            for _ in 0..len {
+                list.push(read_payload(reader, element_id)?);
+            }
+            Tag::List(list)
+        }
+        10 => {
+            let mut map = HashMap::new();
+            loop {
+                let id = read_i8(reader)?;
+                if id == 0 {
+                    break;
+                }
+                let name = read_string(reader)?;
+                map.insert(name, read_payload(reader, id)?);
+            }
+            Tag::Compound(map)
+        }
+        11 => {
+            let len = read_len(reader)?;
+            let mut ints = Vec::new();
+            for _ in 0..len {
+                ints.push(read_i32(reader)?);
+            }
+            Tag::IntArray(ints)
+        }
+        12 => {
+            let len = read_len(reader)?;
+            let mut longs = Vec::new();
+            for _ in 0..len {
+                longs.push(i64::from_be_bytes(read_array(reader)?));
+            }
+            Tag::LongArray(longs)
+        }
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unknown nbt tag id {}", id),
+            ))
+        }
+    })
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut buf = [0; N];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn read_i8(reader: &mut impl Read) -> io::Result<i8> {
+    Ok(read_array::<1>(reader)?[0] as i8)
+}
+
+fn read_i32(reader: &mut impl Read) -> io::Result<i32> {
+    Ok(i32::from_be_bytes(read_array(reader)?))
+}
+
+fn read_len(reader: &mut impl Read) -> io::Result<usize> {
+    let len = read_i32(reader)?;
+    usize::try_from(len).map_err(|_| {
+        io::Error::new(io::ErrorKind::InvalidData, "negative nbt length")
+    })
+}
+
+fn read_string(reader: &mut impl Read) -> io::Result<String> {
+    let len = u16::from_be_bytes(read_array(reader)?);
+    let mut bytes = vec![0; len as usize];
+    reader.read_exact(&mut bytes)?;
+    // NBT uses modified UTF-8; block ids are plain ASCII in practice
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
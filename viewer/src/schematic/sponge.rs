@@ -0,0 +1,134 @@
+//! Import of Sponge schematics (`.schem`, versions 2 and 3) as written by
+//! WorldEdit and most other Minecraft tools.
+
+use std::io::BufRead;
+
+use bevy::{log::warn, math::UVec3, platform::collections::HashSet};
+
+use crate::{
+    block::BuiltBlockID,
+    schematic::{
+        nbt::{self, Tag},
+        palette_index, Schematic, SchematicError, MAX_VOLUME,
+    },
+};
+
+/// Map a Minecraft block state such as `minecraft:grass_block[snowy=false]`
+/// to the closest block we can render.
+pub fn block_from_minecraft_id(id: &str) -> Option<BuiltBlockID> {
+    let name = id.split('[').next().unwrap_or(id);
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    Some(match name {
+        "air" | "cave_air" | "void_air" => BuiltBlockID::Air,
+        "bricks" => BuiltBlockID::Brick,
+        "dirt" | "coarse_dirt" => BuiltBlockID::Dirt,
+        "grass_block" => BuiltBlockID::Grass,
+        "oak_planks" => BuiltBlockID::PlanksOak,
+        "orange_wool" => BuiltBlockID::WoolColoredOrange,
+        _ => return None,
+    })
+}
+
+/// Read a Sponge schematic. Unknown Minecraft blocks become air and are
+/// reported once each.
+pub fn read(reader: impl BufRead) -> Result<Schematic, SchematicError> {
+    let (_, root) = nbt::read(reader)?;
+
+    // v3 wraps everything in a `Schematic` compound, v2 is the root itself
+    let schematic = root.get("Schematic").unwrap_or(&root);
+
+    let version = int(schematic, "Version")?;
+    let (palette_tag, data) = match version {
+        2 => (schematic.get("Palette"), schematic.get("BlockData")),
+        3 => {
+            let blocks = schematic
+                .get("Blocks")
+                .ok_or(SchematicError::Malformed("missing Blocks"))?;
+            (blocks.get("Palette"), blocks.get("Data"))
+        }
+        _ => return Err(SchematicError::UnsupportedVersion(version as u32)),
+    };
+
+    // Sizes are stored as unsigned shorts
+    let size = UVec3::new(
+        int(schematic, "Width")? as u16 as u32,
+        int(schematic, "Height")? as u16 as u32,
+        int(schematic, "Length")? as u16 as u32,
+    );
+
+    let palette_tag = palette_tag
+        .and_then(Tag::as_compound)
+        .ok_or(SchematicError::Malformed("missing Palette"))?;
+    let data = data
+        .and_then(Tag::as_byte_array)
+        .ok_or(SchematicError::Malformed("missing block data"))?;
+
+    // Sponge palette ids -> our palette indices
+    let mut remap = vec![0u16; palette_tag.len()];
+    let mut palette = vec![BuiltBlockID::Air];
+    let mut unknown = HashSet::new();
+
+    for (id, tag) in palette_tag {
+        let sponge_index = tag
+            .as_i64()
+            .and_then(|i| usize::try_from(i).ok())
+            .filter(|&i| i < remap.len())
+            .ok_or(SchematicError::Malformed("palette index"))?;
+
+        let block = block_from_minecraft_id(id).unwrap_or_else(|| {
+            unknown.insert(id.split('[').next().unwrap_or(id).to_string());
+            BuiltBlockID::Air
+        });
+        remap[sponge_index] = palette_index(&mut palette, block);
+    }
+
+    for id in unknown {
+        warn!("Unsupported block in schematic, replaced by air: {}", id);
+    }
+
+    // Every block takes at least one byte of data
+    let count = size.x as u64 * size.y as u64 * size.z as u64;
+    if count > MAX_VOLUME {
+        return Err(SchematicError::Malformed("size"));
+    }
+    if count > data.len() as u64 {
+        return Err(SchematicError::Malformed("truncated block data"));
+    }
+    let count = count as usize;
+    let mut blocks = Vec::with_capacity(count);
+    let mut bytes = data.iter().map(|&b| b as u8);
+
+    while blocks.len() < count {
+        let sponge_index = read_varint(&mut bytes)
+            .ok_or(SchematicError::Malformed("truncated block data"))?;
+        let index = remap
+            .get(sponge_index as usize)
+            .ok_or(SchematicError::Malformed("palette index out of range"))?;
+        blocks.push(*index);
+    }
+
+    Ok(Schematic {
+        size,
+        palette,
+        blocks,
+    })
+}
+
+fn int(tag: &Tag, name: &'static str) -> Result<i64, SchematicError> {
+    tag.get(name)
+        .and_then(Tag::as_i64)
+        .ok_or(SchematicError::Malformed(name))
+}
+
+/// Block data is a sequence of unsigned LEB128 varints
+fn read_varint(bytes: &mut impl Iterator<Item = u8>) -> Option<u32> {
+    let mut value = 0u32;
+    for shift in (0..35).step_by(7) {
+        let byte = bytes.next()?;
+        value |= ((byte & 0x7f) as u32) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
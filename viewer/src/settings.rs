@@ -0,0 +1,216 @@
+use std::{fmt, fs, io, path::Path};
+
+use bevy::{
+    ecs::{
+        change_detection::DetectChanges,
+        entity::Entity,
+        query::With,
+        resource::Resource,
+        system::{Commands, Query, Res, ResMut},
+    },
+    input::{keyboard::KeyCode, ButtonInput},
+    log::warn,
+    pbr::{DistanceFog, FogFalloff},
+    render::camera::{ClearColor, Projection},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    chunk::SIZE,
+    simple_control::{Player, PlayerCamera},
+};
+
+/// Where [`RenderSettings`] are read from and saved to, relative to the
+/// working directory
+pub const SETTINGS_PATH: &str = "settings.ron";
+
+const MIN_VIEW_DISTANCE: u32 = 2;
+const MAX_VIEW_DISTANCE: u32 = 32;
+const MIN_SENSITIVITY: f32 = 0.0005;
+const MAX_SENSITIVITY: f32 = 0.02;
+
+/// Video and control settings, edited live and saved whenever they change.
+/// Missing fields in the file fall back to their defaults.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderSettings {
+    /// In chunks, measured horizontally from the camera's chunk. Chunks
+    /// further away are hidden, and unloaded a little further still by
+    /// `chunk::streaming::stream_chunks`.
+    #[serde(alias = "render_distance")]
+    pub view_distance: u32,
+    /// Where the fog begins, as a fraction of the view distance
+    pub fog_start: f32,
+    /// Vertical field of view in degrees
+    pub fov: f32,
+    /// Radians turned per pixel of mouse movement
+    pub sensitivity: f32,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            view_distance: 8,
+            fog_start: 0.7,
+            fov: 70.0,
+            sensitivity: 0.003,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+    Serialize(ron::Error),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::Io(err) => write!(f, "io error: {}", err),
+            SettingsError::Parse(err) => write!(f, "parse error: {}", err),
+            SettingsError::Serialize(err) => {
+                write!(f, "serialize error: {}", err)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+impl From<io::Error> for SettingsError {
+    fn from(err: io::Error) -> Self {
+        SettingsError::Io(err)
+    }
+}
+
+impl RenderSettings {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SettingsError> {
+        let text = fs::read_to_string(path)?;
+        let settings: Self =
+            ron::from_str(&text).map_err(SettingsError::Parse)?;
+        Ok(settings.clamped())
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SettingsError> {
+        let text =
+            ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+                .map_err(SettingsError::Serialize)?;
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// Keep values from a hand edited file in a usable range
+    pub fn clamped(mut self) -> Self {
+        self.view_distance = self
+            .view_distance
+            .clamp(MIN_VIEW_DISTANCE, MAX_VIEW_DISTANCE);
+        self.fog_start = self.fog_start.clamp(0.0, 1.0);
+        self.fov = self.fov.clamp(30.0, 110.0);
+        self.sensitivity =
+            self.sensitivity.clamp(MIN_SENSITIVITY, MAX_SENSITIVITY);
+        self
+    }
+
+    /// Distance in blocks where the fog is complete
+    pub fn fog_end(&self) -> f32 {
+        (self.view_distance as usize * SIZE) as f32
+    }
+
+    pub fn fog_start(&self) -> f32 {
+        self.fog_end() * self.fog_start
+    }
+
+    /// Far enough to reach the corners of the furthest chunks
+    pub fn far_plane(&self) -> f32 {
+        ((self.view_distance as usize + 1) * SIZE) as f32 * 2.0
+    }
+}
+
+/// Read the settings file, falling back to the defaults if it is missing
+/// or broken
+pub fn setup(mut commands: Commands) {
+    let settings = match RenderSettings::load(SETTINGS_PATH) {
+        Ok(settings) => settings,
+        Err(SettingsError::Io(err))
+            if err.kind() == io::ErrorKind::NotFound =>
+        {
+            RenderSettings::default()
+        }
+        Err(err) => {
+            warn!("Could not read {}: {}", SETTINGS_PATH, err);
+            RenderSettings::default()
+        }
+    };
+    commands.insert_resource(settings);
+}
+
+/// `-` and `=` shrink and grow the view distance
+pub fn adjust_view_distance(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<RenderSettings>,
+) {
+    let view_distance = if keys.just_pressed(KeyCode::Minus) {
+        settings.view_distance.saturating_sub(1)
+    } else if keys.just_pressed(KeyCode::Equal) {
+        settings.view_distance + 1
+    } else {
+        return;
+    };
+    settings.view_distance =
+        view_distance.clamp(MIN_VIEW_DISTANCE, MAX_VIEW_DISTANCE);
+}
+
+/// Push the settings into the camera projection, fog and player controls.
+/// Also picks up a camera spawned after the settings last changed.
+pub fn apply_render_settings(
+    mut commands: Commands,
+    settings: Res<RenderSettings>,
+    clear_color: Res<ClearColor>,
+    mut cameras: Query<
+        (Entity, &mut Projection, Option<&mut DistanceFog>),
+        With<PlayerCamera>,
+    >,
+    mut players: Query<&mut Player>,
+) {
+    for (entity, mut projection, fog) in &mut cameras {
+        let falloff = FogFalloff::Linear {
+            start: settings.fog_start(),
+            end: settings.fog_end(),
+        };
+        match fog {
+            Some(mut fog) if settings.is_changed() => fog.falloff = falloff,
+            Some(_) => continue,
+            None => {
+                // The day/night cycle keeps the color in sync with the sky
+                commands.entity(entity).insert(DistanceFog {
+                    color: clear_color.0,
+                    falloff,
+                    ..Default::default()
+                });
+            }
+        }
+
+        if let Projection::Perspective(perspective) = projection.as_mut() {
+            perspective.fov = settings.fov.to_radians();
+            perspective.far = settings.far_plane();
+        }
+    }
+
+    if settings.is_changed() {
+        for mut player in &mut players {
+            player.sensitivity = settings.sensitivity;
+        }
+    }
+}
+
+pub fn save_render_settings(settings: Res<RenderSettings>) {
+    // Nothing to save for the values just loaded
+    if !settings.is_changed() || settings.is_added() {
+        return;
+    }
+    if let Err(err) = settings.save(SETTINGS_PATH) {
+        warn!("Could not save {}: {}", SETTINGS_PATH, err);
+    }
+}
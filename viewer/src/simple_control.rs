@@ -1,13 +1,22 @@
+use bevy::audio::SpatialListener;
 use bevy::input::mouse::MouseMotion;
 use bevy::prelude::*;
 use bevy::window::{CursorGrabMode, PrimaryWindow};
 
+use crate::components::player_stats::PlayerStats;
+use crate::entity::riding::Rides;
+
+/// Distance from the player's eyes (the `Player` transform) to their feet
+pub const PLAYER_EYE_HEIGHT: f32 = 1.62;
+
 #[derive(Component)]
 pub struct Player {
     pub speed: f32,
     pub sensitivity: f32,
     pub yaw: f32,
     pub pitch: f32,
+    /// Total horizontal distance moved, used for footsteps
+    pub distance_walked: f32,
 }
 
 #[derive(Component)]
@@ -35,7 +44,9 @@ pub fn setup<M: Asset + TypePath>(
                 sensitivity: 0.003,
                 yaw: initial_yaw,
                 pitch: initial_pitch,
+                distance_walked: 0.0,
             },
+            PlayerStats::default(),
             transform,
             Visibility::default(),
         ))
@@ -51,6 +62,7 @@ pub fn setup<M: Asset + TypePath>(
                         aspect_ratio: 1.0,
                     }),
                     PlayerCamera,
+                    SpatialListener::new(0.3),
                 ));
             },
         );
@@ -74,12 +86,14 @@ pub fn cursor_grab_system(
     }
 }
 
+/// Riding players steer their mount instead, see
+/// [`drive_mounts`](crate::entity::riding::drive_mounts)
 pub fn player_movement_system(
     time: Res<Time>,
     keys: Res<ButtonInput<KeyCode>>,
-    mut player_query: Query<(&Player, &mut Transform), Without<PlayerCamera>>,
+    mut player_query: Query<(&mut Player, &mut Transform, Has<Rides>), Without<PlayerCamera>>,
 ) {
-    if let Ok((player, mut transform)) = player_query.single_mut() {
+    if let Ok((mut player, mut transform, false)) = player_query.single_mut() {
         let mut velocity = Vec3::ZERO;
         let local_z = transform.local_z();
         let forward = -Vec3::new(local_z.x, 0.0, local_z.z);
@@ -107,19 +121,21 @@ pub fn player_movement_system(
 
         if velocity.length() > 0.0 {
             velocity = velocity.normalize();
-            transform.translation += velocity * player.speed * time.delta_secs();
+            let delta = velocity * player.speed * time.delta_secs();
+            transform.translation += delta;
+            player.distance_walked += delta.xz().length();
         }
     }
 }
 
 pub fn player_look_system(
     mut mouse_motion_events: EventReader<MouseMotion>,
-    mut player_query: Query<(&mut Player, &mut Transform), Without<PlayerCamera>>,
+    mut player_query: Query<(&mut Player, &mut Transform, Has<Rides>), Without<PlayerCamera>>,
     mut windows: Query<&Window, With<PrimaryWindow>>,
 ) {
     if let Ok(window) = windows.single_mut() {
         if window.cursor_options.grab_mode == CursorGrabMode::Locked {
-            if let Ok((mut player, mut transform)) = player_query.single_mut() {
+            if let Ok((mut player, mut transform, riding)) = player_query.single_mut() {
                 for ev in mouse_motion_events.read() {
                     player.yaw -= ev.delta.x * player.sensitivity;
                     player.pitch -= ev.delta.y * player.sensitivity;
@@ -127,8 +143,10 @@ pub fn player_look_system(
                     let max_pitch = 85.0_f32.to_radians();
                     player.pitch = player.pitch.clamp(-max_pitch, max_pitch);
 
+                    // A mount turns to the player's yaw and carries it
+                    let yaw = if riding { 0.0 } else { player.yaw };
                     transform.rotation =
-                        Quat::from_euler(EulerRot::YXZ, player.yaw, player.pitch, 0.0);
+                        Quat::from_euler(EulerRot::YXZ, yaw, player.pitch, 0.0);
                 }
             }
         }
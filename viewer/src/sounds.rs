@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use bevy::{
+    asset::{AssetServer, Handle},
+    audio::{AudioPlayer, AudioSource, PlaybackSettings, Volume},
+    ecs::{
+        event::EventReader,
+        query::Without,
+        resource::Resource,
+        system::{Commands, Local, Query, Res},
+    },
+    math::Vec3,
+    transform::components::Transform,
+};
+use rand::Rng;
+use strum::IntoEnumIterator;
+
+use crate::{
+    block::BuiltBlockID,
+    chunk::{BlockChangeCause, BlockChanged, WorldPos, WorldReader},
+    simple_control::{Player, PlayerCamera, PLAYER_EYE_HEIGHT},
+};
+
+/// Horizontal distance walked between two footsteps
+const STRIDE: f32 = 1.6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, strum_macros::EnumIter)]
+pub enum SoundGroup {
+    Grass,
+    Stone,
+    Wood,
+    Cloth,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockSoundKind {
+    Break,
+    Place,
+    Step,
+}
+
+impl SoundGroup {
+    /// Wood and cloth reuse the closest shipped sample until they get
+    /// their own recordings.
+    pub fn sample_path(self) -> &'static str {
+        match self {
+            SoundGroup::Grass | SoundGroup::Cloth => "sounds/dig-grass1.ogg",
+            SoundGroup::Stone | SoundGroup::Wood => "sounds/dig-stone1.ogg",
+        }
+    }
+
+    pub fn pitch(self) -> f32 {
+        match self {
+            SoundGroup::Grass | SoundGroup::Stone => 1.0,
+            SoundGroup::Wood => 0.8,
+            SoundGroup::Cloth => 1.2,
+        }
+    }
+}
+
+impl BlockSoundKind {
+    /// `(volume, pitch)` multipliers, following Minecraft's block sounds
+    pub fn volume_pitch(self) -> (f32, f32) {
+        match self {
+            BlockSoundKind::Break | BlockSoundKind::Place => (1.0, 0.8),
+            BlockSoundKind::Step => (0.15, 1.0),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct BlockSounds(pub HashMap<SoundGroup, Handle<AudioSource>>);
+
+pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let sounds = SoundGroup::iter()
+        .map(|group| (group, asset_server.load(group.sample_path())))
+        .collect();
+
+    commands.insert_resource(BlockSounds(sounds));
+}
+
+/// Spawn a one-shot spatial sound at the center of `world_pos`
+pub fn play_block_sound(
+    commands: &mut Commands,
+    sounds: &BlockSounds,
+    block: BuiltBlockID,
+    kind: BlockSoundKind,
+    world_pos: WorldPos,
+) {
+    let Some(group) = block.sound_group() else {
+        return;
+    };
+    let Some(source) = sounds.0.get(&group) else {
+        return;
+    };
+
+    let (volume, pitch) = kind.volume_pitch();
+    let variation = rand::thread_rng().gen_range(0.9..1.1);
+
+    commands.spawn((
+        AudioPlayer(source.clone()),
+        PlaybackSettings::DESPAWN
+            .with_spatial(true)
+            .with_volume(Volume::Linear(volume))
+            .with_speed(group.pitch() * pitch * variation),
+        Transform::from_xyz(
+            world_pos.x as f32,
+            world_pos.y as f32,
+            world_pos.z as f32,
+        ),
+    ));
+}
+
+pub fn play_block_change_sounds(
+    mut commands: Commands,
+    mut block_changed_events: EventReader<BlockChanged>,
+    sounds: Res<BlockSounds>,
+) {
+    for event in block_changed_events.read() {
+        let (block, kind) = match event.cause {
+            BlockChangeCause::PlayerBreak => (event.old, BlockSoundKind::Break),
+            BlockChangeCause::PlayerPlace => (event.new, BlockSoundKind::Place),
+            _ => continue,
+        };
+
+        play_block_sound(&mut commands, &sounds, block, kind, event.pos);
+    }
+}
+
+/// Plays a step each time the player has walked another `STRIDE` over
+/// solid ground, using [`Player::distance_walked`] from the movement system.
+pub fn play_footstep_sounds(
+    mut commands: Commands,
+    mut last_step: Local<f32>,
+    player_query: Query<(&Player, &Transform), Without<PlayerCamera>>,
+    reader: WorldReader,
+    sounds: Res<BlockSounds>,
+) {
+    let Ok((player, transform)) = player_query.single() else {
+        return;
+    };
+
+    if player.distance_walked - *last_step < STRIDE {
+        return;
+    }
+    *last_step = player.distance_walked;
+
+    let feet = transform.translation - Vec3::Y * PLAYER_EYE_HEIGHT;
+    let ground = WorldPos {
+        x: feet.x.round() as i32,
+        y: (feet.y - 0.5).round() as i32,
+        z: feet.z.round() as i32,
+    };
+
+    if let Some(block) = reader.get_block(ground).block() {
+        play_block_sound(
+            &mut commands,
+            &sounds,
+            block,
+            BlockSoundKind::Step,
+            ground,
+        );
+    }
+}
@@ -1,34 +1,216 @@
+use std::collections::HashMap;
+
 use bevy::{
-    asset::Assets, color::Alpha, ecs::{
+    asset::{Asset, Assets, Handle},
+    color::Color,
+    ecs::{
+        change_detection::DetectChanges,
+        component::Component,
         entity::Entity,
         event::{Event, EventReader},
-        query::{With, Without},
-        system::{Commands, Query, ResMut},
-    }, pbr::{wireframe::Wireframe, StandardMaterial}, render::mesh::Mesh3d
+        query::Without,
+        resource::Resource,
+        system::{Commands, Query, Res, ResMut},
+    },
+    input::{keyboard::KeyCode, ButtonInput},
+    pbr::{
+        wireframe::WireframeConfig, Material, MeshMaterial3d, StandardMaterial,
+    },
+    reflect::TypePath,
+    render::render_resource::{AsBindGroup, ShaderRef},
+    utils::default,
 };
 
+use crate::chunk::{Chunk, ChunkPos};
+
+const NORMALS_SHADER_ASSET_PATH: &str = "shaders/debug_normals.wgsl";
+
+/// How meshes are drawn. Everything except `Normal` and `WireframeOverlay`
+/// swaps materials out, keeping the originals in [`StashedMaterial`] so
+/// they come back untouched.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DebugRenderMode {
+    #[default]
+    Normal,
+    /// Wireframe drawn on top of the regular materials
+    WireframeOverlay,
+    /// Only the wireframe, materials are hidden
+    WireframeOnly,
+    /// World space normals as colors
+    Normals,
+    /// Every chunk in its own flat color
+    ChunkTint,
+}
+
+impl DebugRenderMode {
+    pub fn next(self) -> Self {
+        match self {
+            DebugRenderMode::Normal => DebugRenderMode::WireframeOverlay,
+            DebugRenderMode::WireframeOverlay => DebugRenderMode::WireframeOnly,
+            DebugRenderMode::WireframeOnly => DebugRenderMode::Normals,
+            DebugRenderMode::Normals => DebugRenderMode::ChunkTint,
+            DebugRenderMode::ChunkTint => DebugRenderMode::Normal,
+        }
+    }
+
+    fn swaps_materials(self) -> bool {
+        !matches!(
+            self,
+            DebugRenderMode::Normal | DebugRenderMode::WireframeOverlay
+        )
+    }
+
+    fn shows_wireframe(self) -> bool {
+        matches!(
+            self,
+            DebugRenderMode::WireframeOverlay | DebugRenderMode::WireframeOnly
+        )
+    }
+}
+
+/// Switch between [`DebugRenderMode::Normal`] and the wireframe overlay
 #[derive(Event, Default)]
 pub struct ToggleWireframe;
 
-/// Function to toggle wireframe (seeing the vertices and indices of the mesh).
+#[derive(Event)]
+pub struct SetDebugRenderMode(pub DebugRenderMode);
+
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone, Default)]
+pub struct NormalsMaterial {}
+
+impl Material for NormalsMaterial {
+    fn fragment_shader() -> ShaderRef {
+        NORMALS_SHADER_ASSET_PATH.into()
+    }
+}
+
+/// The material an entity had before a debug mode replaced it
+#[derive(Component)]
+pub struct StashedMaterial<M: Material>(pub Handle<M>);
+
+/// Marks entities whose current material was put there by a debug mode
+#[derive(Component)]
+pub struct DebugMaterial;
+
+#[derive(Resource)]
+pub struct DebugMaterials {
+    pub normals: Handle<NormalsMaterial>,
+    pub chunk_tints: HashMap<ChunkPos, Handle<StandardMaterial>>,
+}
+
+pub fn setup(
+    mut commands: Commands,
+    mut normals_materials: ResMut<Assets<NormalsMaterial>>,
+) {
+    commands.insert_resource(DebugRenderMode::default());
+    commands.insert_resource(DebugMaterials {
+        normals: normals_materials.add(NormalsMaterial::default()),
+        chunk_tints: HashMap::new(),
+    });
+}
+
+/// F4 cycles through the debug render modes
+pub fn cycle_debug_render_mode(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut mode: ResMut<DebugRenderMode>,
+) {
+    if keys.just_pressed(KeyCode::F4) {
+        *mode = mode.next();
+    }
+}
+
 pub fn toggle_wireframe(
+    mut mode: ResMut<DebugRenderMode>,
+    mut toggle_events: EventReader<ToggleWireframe>,
+    mut set_events: EventReader<SetDebugRenderMode>,
+) {
+    for _ in toggle_events.read() {
+        *mode = if *mode == DebugRenderMode::WireframeOverlay {
+            DebugRenderMode::Normal
+        } else {
+            DebugRenderMode::WireframeOverlay
+        };
+    }
+    for event in set_events.read() {
+        *mode = event.0;
+    }
+}
+
+pub fn apply_wireframe_config(
+    mode: Res<DebugRenderMode>,
+    mut config: ResMut<WireframeConfig>,
+) {
+    if mode.is_changed() {
+        config.global = mode.shows_wireframe();
+    }
+}
+
+type SwappableMesh<'a, M> = (Entity, &'a MeshMaterial3d<M>, Option<&'a Chunk>);
+
+/// Swap `M` materials for the current debug mode. Registered once per
+/// material type, e.g. for `BindlessMaterial` chunks and glTF
+/// `StandardMaterial`s.
+pub fn swap_debug_materials<M: Material>(
     mut commands: Commands,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    with: Query<Entity, With<Wireframe>>,
-    without: Query<Entity, (Without<Wireframe>, With<Mesh3d>)>,
-    mut events: EventReader<ToggleWireframe>,
+    mode: Res<DebugRenderMode>,
+    mut debug_materials: ResMut<DebugMaterials>,
+    mut standard_materials: ResMut<Assets<StandardMaterial>>,
+    originals: Query<SwappableMesh<M>, Without<DebugMaterial>>,
+    stashed: Query<(Entity, &StashedMaterial<M>)>,
 ) {
-    for _ in events.read() {
-        if let Ok(ent) = with.single() {
-            commands.entity(ent).remove::<Wireframe>();
-            for (_, material) in materials.iter_mut() {
-                material.base_color.set_alpha(1.0);
+    // Put everything back first, the new mode is applied next frame
+    if mode.is_changed() {
+        for (entity, stash) in &stashed {
+            commands
+                .entity(entity)
+                .remove::<(
+                    DebugMaterial,
+                    StashedMaterial<M>,
+                    MeshMaterial3d<NormalsMaterial>,
+                    MeshMaterial3d<StandardMaterial>,
+                )>()
+                .insert(MeshMaterial3d(stash.0.clone()));
+        }
+        return;
+    }
+    if !mode.swaps_materials() {
+        return;
+    }
+
+    for (entity, material, chunk) in &originals {
+        let mut entity_commands = commands.entity(entity);
+
+        match (*mode, chunk) {
+            (DebugRenderMode::WireframeOnly, _) => {}
+            (DebugRenderMode::Normals, _) => {
+                entity_commands
+                    .insert(MeshMaterial3d(debug_materials.normals.clone()));
             }
-        } else if let Ok(ent) = without.single() {
-            commands.entity(ent).insert(Wireframe);
-            for (_, material) in materials.iter_mut() {
-                material.base_color.set_alpha(0.0);
+            (DebugRenderMode::ChunkTint, Some(chunk)) => {
+                let tint = debug_materials
+                    .chunk_tints
+                    .entry(chunk.pos())
+                    .or_insert_with(|| {
+                        standard_materials.add(StandardMaterial {
+                            base_color: chunk_color(chunk.pos()),
+                            ..default()
+                        })
+                    });
+                entity_commands.insert(MeshMaterial3d(tint.clone()));
             }
+            _ => continue,
         }
+
+        entity_commands
+            .remove::<MeshMaterial3d<M>>()
+            .insert((StashedMaterial(material.0.clone()), DebugMaterial));
     }
 }
+
+fn chunk_color(pos: ChunkPos) -> Color {
+    // Cheap hash so neighbouring chunks get clearly different hues
+    let hash = (pos.x.wrapping_mul(73_856_093)
+        ^ pos.y.wrapping_mul(19_349_663)
+        ^ pos.z.wrapping_mul(83_492_791)) as u32;
+    Color::hsl((hash % 360) as f32, 0.65, 0.55)
+}
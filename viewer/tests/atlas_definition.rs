@@ -0,0 +1,89 @@
+use viewer::{
+    atlas_definition::{AtlasDefinition, AtlasDefinitionError},
+    region::Region,
+};
+
+fn parse(
+    regions: &str,
+    grid: &str,
+) -> Result<AtlasDefinition, AtlasDefinitionError> {
+    AtlasDefinition::from_ron(&format!(
+        r#"(image: "atlas.png", width: 64, height: 32, regions: [{}], grid: {})"#,
+        regions, grid
+    ))
+}
+
+#[test]
+fn grid_tiles_follow_named_regions() {
+    let definition = parse(
+        r#"(name: "logo", x: 0, y: 0, w: 16, h: 16)"#,
+        r#"Some((tile_width: 8, tile_height: 8, columns: 2, rows: 2,
+            padding: 1, offset_x: 20, names: ["first"]))"#,
+    )
+    .unwrap();
+
+    let regions = definition.regions();
+    assert_eq!(regions.len(), 5);
+    assert_eq!(regions[0].0, Some("logo"));
+    assert_eq!(regions[1].0, Some("first"));
+    assert_eq!(regions[2].0, None);
+    assert_eq!(
+        regions[4].1,
+        Region {
+            x: 29,
+            y: 9,
+            w: 8,
+            h: 8
+        }
+    );
+    assert_eq!(definition.indices()["first"], 1);
+}
+
+#[test]
+fn regions_outside_the_image_are_rejected() {
+    assert!(matches!(
+        parse(r#"(name: "wide", x: 60, y: 0, w: 5, h: 1)"#, "None"),
+        Err(AtlasDefinitionError::OutOfBounds(name)) if name == "wide"
+    ));
+    // Only the second row of the grid sticks out
+    assert!(matches!(
+        parse(
+            "",
+            "Some((tile_width: 16, tile_height: 16, columns: 4, rows: 3))"
+        ),
+        Err(AtlasDefinitionError::OutOfBounds(name)) if name == "8"
+    ));
+    // Touching the edges is fine
+    assert!(parse(r#"(name: "all", x: 0, y: 0, w: 64, h: 32)"#, "None").is_ok());
+}
+
+#[test]
+fn overflowing_regions_are_out_of_bounds() {
+    assert!(matches!(
+        parse(
+            r#"(name: "huge", x: 4294967295, y: 0, w: 2, h: 1)"#,
+            "None"
+        ),
+        Err(AtlasDefinitionError::OutOfBounds(name)) if name == "huge"
+    ));
+    // The third tile's position doesn't fit in a u32
+    assert!(matches!(
+        parse(
+            "",
+            "Some((tile_width: 2147483648, tile_height: 1, columns: 3, \
+             rows: 1, names: [\"a\", \"b\", \"c\"]))"
+        ),
+        Err(AtlasDefinitionError::OutOfBounds(name)) if name == "a"
+    ));
+}
+
+#[test]
+fn overlapping_regions_are_allowed() {
+    // A bar and the first slot inside it
+    assert!(parse(
+        r#"(name: "bar", x: 0, y: 0, w: 40, h: 8),
+           (name: "slot", x: 1, y: 1, w: 6, h: 6)"#,
+        "None"
+    )
+    .is_ok());
+}
@@ -0,0 +1,176 @@
+use bevy::{ecs::system::RunSystemOnce, prelude::*};
+use viewer::{
+    bindless_material::BindlessMaterial,
+    block::{BlockRegistry, BuiltBlockID},
+    built_block_mesh::{get_texture, isotropic_mesh},
+    chunk::{
+        self, streaming, BlockChangeCause, BlockChanged, BlockLookup,
+        BlockMaterial, ChunkPos, SetBlockEvent, World, WorldPos, WorldReader,
+        SIZE,
+    },
+    settings::RenderSettings,
+    simple_control::PlayerCamera,
+};
+
+/// Camera in the chunk at the origin, with a view distance of two and a
+/// brick chunk at every x in `xs`
+fn app_with_chunks(xs: &[i32]) -> (App, Entity) {
+    let missing = isotropic_mesh(get_texture("missing_tile.png"));
+    let mut app = App::new();
+    app.init_resource::<Assets<Mesh>>()
+        .insert_resource(BlockRegistry {
+            grass: missing.clone(),
+            brick: missing.clone(),
+            dirt: missing.clone(),
+            planks_oak: missing.clone(),
+            wool_colored_orange: missing,
+        })
+        .insert_resource(World::new())
+        .insert_resource(BlockMaterial(Handle::default()))
+        .insert_resource(RenderSettings {
+            view_distance: 2,
+            ..default()
+        })
+        .add_event::<SetBlockEvent>()
+        .add_event::<BlockChanged>()
+        .add_systems(
+            Update,
+            (
+                chunk::handle_set_block_events,
+                chunk::flush_unspawned_chunks,
+                streaming::stream_chunks,
+            )
+                .chain(),
+        );
+    let camera = app
+        .world_mut()
+        .spawn((PlayerCamera, GlobalTransform::from_xyz(4.0, 4.0, 4.0)))
+        .id();
+
+    let xs = xs.to_vec();
+    app.world_mut()
+        .run_system_once(
+            move |mut world: ResMut<World>,
+                  mut commands: Commands,
+                  mut meshes: ResMut<Assets<Mesh>>,
+                  breg: Res<BlockRegistry>| {
+                for &x in &xs {
+                    chunk::spawn_chunk(
+                        &mut world,
+                        &mut commands,
+                        &mut meshes,
+                        &Handle::<BindlessMaterial>::default(),
+                        &breg,
+                        ChunkPos { x, y: 0, z: 0 },
+                        Some(BuiltBlockID::Brick),
+                    );
+                }
+            },
+        )
+        .unwrap();
+    app.update();
+    (app, camera)
+}
+
+fn is_loaded(app: &App, x: i32) -> bool {
+    app.world()
+        .resource::<World>()
+        .is_loaded(ChunkPos { x, y: 0, z: 0 })
+}
+
+fn read(app: &mut App, world_pos: WorldPos) -> BlockLookup {
+    app.world_mut()
+        .run_system_once(move |reader: WorldReader| reader.get_block(world_pos))
+        .unwrap()
+}
+
+fn move_camera(app: &mut App, camera: Entity, chunk_x: i32) {
+    let x = (chunk_x * SIZE as i32) as f32 + 4.0;
+    app.world_mut()
+        .entity_mut(camera)
+        .insert(GlobalTransform::from_xyz(x, 4.0, 4.0));
+    app.update();
+}
+
+#[test]
+fn far_chunks_are_unloaded_past_the_margin() {
+    let (app, _) = app_with_chunks(&[0, 2, 3, 4, -4]);
+
+    assert!(is_loaded(&app, 0));
+    assert!(is_loaded(&app, 2));
+    // Within the margin
+    assert!(is_loaded(&app, 3));
+    assert!(!is_loaded(&app, 4));
+    assert!(!is_loaded(&app, -4));
+    let world = app.world().resource::<World>();
+    assert!(world.chunk_entity(ChunkPos { x: 4, y: 0, z: 0 }).is_none());
+    assert_eq!(world.loaded_chunk_count(), 3);
+}
+
+#[test]
+fn chunks_come_back_with_their_blocks() {
+    let (mut app, camera) = app_with_chunks(&[0, 4]);
+    let far = WorldPos {
+        x: 4 * SIZE as i32 + 1,
+        y: 1,
+        z: 1,
+    };
+    assert_eq!(read(&mut app, far), BlockLookup::Unloaded);
+
+    move_camera(&mut app, camera, 3);
+    assert!(is_loaded(&app, 4));
+    assert_eq!(
+        read(&mut app, far),
+        BlockLookup::Loaded(BuiltBlockID::Brick)
+    );
+}
+
+#[test]
+fn writing_to_an_unloaded_chunk_keeps_its_blocks() {
+    let (mut app, camera) = app_with_chunks(&[0, 4]);
+    let far = WorldPos {
+        x: 4 * SIZE as i32,
+        y: 0,
+        z: 0,
+    };
+    app.world_mut().send_event(SetBlockEvent {
+        world_pos: far,
+        block: BuiltBlockID::Dirt,
+        cause: BlockChangeCause::Unspecified,
+    });
+    app.update();
+    // Still out of range, so it goes again
+    assert!(!is_loaded(&app, 4));
+
+    move_camera(&mut app, camera, 4);
+    assert_eq!(read(&mut app, far), BlockLookup::Loaded(BuiltBlockID::Dirt));
+    assert_eq!(
+        read(
+            &mut app,
+            WorldPos {
+                x: far.x + 1,
+                ..far
+            }
+        ),
+        BlockLookup::Loaded(BuiltBlockID::Brick)
+    );
+}
+
+#[test]
+fn hand_edited_settings_are_clamped() {
+    let settings = RenderSettings {
+        view_distance: 1000,
+        sensitivity: 10.0,
+        ..default()
+    }
+    .clamped();
+    assert!(settings.view_distance <= 32);
+    assert!(settings.sensitivity < 1.0);
+
+    let settings = RenderSettings {
+        sensitivity: -1.0,
+        ..default()
+    }
+    .clamped();
+    assert!(settings.sensitivity > 0.0);
+}
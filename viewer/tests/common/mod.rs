@@ -0,0 +1,37 @@
+//! Fixtures shared by the model assembly and riding tests
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use viewer::model::{
+    assembly::{self, AttachEntity, DetachEntity, Joints},
+    manifest::{ModelManifest, ModelManifests},
+};
+
+/// App running the attachment systems, with an empty manifest registry
+pub fn assembly_app() -> App {
+    let mut app = App::new();
+    app.add_event::<AttachEntity>()
+        .add_event::<DetachEntity>()
+        .init_resource::<ModelManifests>()
+        .init_resource::<Assets<ModelManifest>>()
+        .add_systems(
+            Update,
+            (assembly::attach_entities, assembly::bind_attachments).chain(),
+        );
+    app
+}
+
+/// An entity with `joint` on a bone entity, returns the entity and the bone
+pub fn spawn_with_joint(
+    app: &mut App,
+    joint: &str,
+    offset: Transform,
+) -> (Entity, Entity) {
+    let entity = app.world_mut().spawn(Transform::default()).id();
+    let bone = app.world_mut().spawn(ChildOf(entity)).id();
+    app.world_mut()
+        .entity_mut(entity)
+        .insert(Joints(HashMap::from([(joint.to_string(), (bone, offset))])));
+    (entity, bone)
+}
@@ -0,0 +1,165 @@
+use bevy::{ecs::system::RunSystemOnce, prelude::*};
+use viewer::{
+    bindless_material::BindlessMaterial,
+    block::{BlockRegistry, BuiltBlockID},
+    built_block_mesh::{get_texture, isotropic_mesh},
+    chunk::{
+        self,
+        culling::{self, ChunkCulling, ChunkFace, FaceConnectivity},
+        BlockChangeCause, BlockChanged, BlockMaterial, ChunkPos, SetBlockEvent,
+        World, WorldPos, SIZE,
+    },
+    settings::RenderSettings,
+    simple_control::PlayerCamera,
+};
+
+const CHUNK_LEN: usize = SIZE * SIZE * SIZE;
+
+fn index(x: usize, y: usize, z: usize) -> usize {
+    x + z * SIZE + y * SIZE * SIZE
+}
+
+#[test]
+fn solid_and_empty_chunks() {
+    let solid = FaceConnectivity::compute(&[BuiltBlockID::Brick; CHUNK_LEN]);
+    assert_eq!(solid, FaceConnectivity::CLOSED);
+    assert!(!solid.connects(ChunkFace::NegX, ChunkFace::PosX));
+
+    let empty = FaceConnectivity::compute(&[BuiltBlockID::Air; CHUNK_LEN]);
+    assert_eq!(empty, FaceConnectivity::OPEN);
+    for a in ChunkFace::ALL {
+        for b in ChunkFace::ALL {
+            assert!(empty.connects(a, b));
+        }
+    }
+}
+
+#[test]
+fn tunnel_connects_only_its_ends() {
+    let mut grid = [BuiltBlockID::Dirt; CHUNK_LEN];
+    for x in 0..SIZE {
+        grid[index(x, 3, 4)] = BuiltBlockID::Air;
+    }
+    // A closed pocket touching no face changes nothing
+    grid[index(5, 5, 1)] = BuiltBlockID::Air;
+
+    let tunnel = FaceConnectivity::compute(&grid);
+    assert!(tunnel.connects(ChunkFace::NegX, ChunkFace::PosX));
+    assert!(tunnel.connects(ChunkFace::PosX, ChunkFace::NegX));
+    for (a, b) in [
+        (ChunkFace::NegX, ChunkFace::PosY),
+        (ChunkFace::NegY, ChunkFace::PosY),
+        (ChunkFace::NegZ, ChunkFace::PosZ),
+        (ChunkFace::PosX, ChunkFace::NegZ),
+    ] {
+        assert!(!tunnel.connects(a, b), "{:?} and {:?}", a, b);
+    }
+}
+
+/// Camera in the empty chunk at the origin, with a view distance of two
+fn app_with_chunks(chunks: Vec<(ChunkPos, BuiltBlockID)>) -> App {
+    let missing = isotropic_mesh(get_texture("missing_tile.png"));
+    let mut app = App::new();
+    app.init_resource::<Assets<Mesh>>()
+        .insert_resource(BlockRegistry {
+            grass: missing.clone(),
+            brick: missing.clone(),
+            dirt: missing.clone(),
+            planks_oak: missing.clone(),
+            wool_colored_orange: missing,
+        })
+        .insert_resource(World::new())
+        .insert_resource(BlockMaterial(Handle::default()))
+        .insert_resource(RenderSettings {
+            view_distance: 2,
+            ..default()
+        })
+        .init_resource::<ChunkCulling>()
+        .add_event::<SetBlockEvent>()
+        .add_event::<BlockChanged>()
+        .add_systems(
+            Update,
+            (
+                chunk::handle_set_block_events,
+                chunk::flush_unspawned_chunks,
+                chunk::update_dirty_chunks,
+                culling::update_visible_chunks,
+            )
+                .chain(),
+        );
+    app.world_mut()
+        .spawn((PlayerCamera, GlobalTransform::from_xyz(4.0, 4.0, 4.0)));
+
+    app.world_mut()
+        .run_system_once(
+            move |mut world: ResMut<World>,
+                  mut commands: Commands,
+                  mut meshes: ResMut<Assets<Mesh>>,
+                  breg: Res<BlockRegistry>| {
+                for &(pos, fill_with) in &chunks {
+                    chunk::spawn_chunk(
+                        &mut world,
+                        &mut commands,
+                        &mut meshes,
+                        &Handle::<BindlessMaterial>::default(),
+                        &breg,
+                        pos,
+                        Some(fill_with),
+                    );
+                }
+            },
+        )
+        .unwrap();
+    app.update();
+    app
+}
+
+fn visible(app: &App, x: i32, z: i32) -> bool {
+    app.world()
+        .resource::<ChunkCulling>()
+        .visible
+        .contains(&ChunkPos { x, y: 0, z })
+}
+
+#[test]
+fn walk_stops_at_solid_chunks_and_view_distance() {
+    let app = app_with_chunks(vec![
+        (ChunkPos { x: 0, y: 0, z: 0 }, BuiltBlockID::Air),
+        (ChunkPos { x: 1, y: 0, z: 0 }, BuiltBlockID::Brick),
+        (ChunkPos { x: 2, y: 0, z: 0 }, BuiltBlockID::Air),
+        (ChunkPos { x: -1, y: 0, z: 0 }, BuiltBlockID::Air),
+        (ChunkPos { x: -2, y: 0, z: 0 }, BuiltBlockID::Air),
+        (ChunkPos { x: -3, y: 0, z: 0 }, BuiltBlockID::Air),
+    ]);
+
+    assert!(visible(&app, 0, 0));
+    // The wall itself is seen, not what lies behind it
+    assert!(visible(&app, 1, 0));
+    assert!(!visible(&app, 2, 0));
+    assert!(visible(&app, -1, 0));
+    assert!(visible(&app, -2, 0));
+    assert!(!visible(&app, -3, 0));
+    // Unloaded chunks are walked through but never reported
+    assert_eq!(app.world().resource::<ChunkCulling>().visible.len(), 4);
+}
+
+#[test]
+fn tunnel_opens_the_way_through_a_wall() {
+    let mut app = app_with_chunks(vec![
+        (ChunkPos { x: 0, y: 0, z: 0 }, BuiltBlockID::Air),
+        (ChunkPos { x: 0, y: 0, z: -1 }, BuiltBlockID::Brick),
+        (ChunkPos { x: 0, y: 0, z: -2 }, BuiltBlockID::Air),
+    ]);
+    assert!(visible(&app, 0, -1));
+    assert!(!visible(&app, 0, -2));
+
+    app.world_mut()
+        .send_event_batch((-(SIZE as i32)..0).map(|z| SetBlockEvent {
+            world_pos: WorldPos { x: 3, y: 3, z },
+            block: BuiltBlockID::Air,
+            cause: BlockChangeCause::PlayerBreak,
+        }));
+    app.update();
+
+    assert!(visible(&app, 0, -2));
+}
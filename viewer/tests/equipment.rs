@@ -0,0 +1,229 @@
+mod common;
+
+use bevy::prelude::*;
+use common::{assembly_app, spawn_with_joint};
+use viewer::{
+    entity::equipment::{
+        self, armor_texture_path, ArmorMaterials, Equipment, EquipmentPiece,
+        EquipmentPieces, EquipmentSlot,
+    },
+    item::Item,
+    model::{
+        assembly::{Attached, Joints},
+        manifest::{Model, ModelManifest, ModelManifests},
+    },
+};
+
+const ZOMBIE: &str = r#"(
+    entity_type: "zombie",
+    model: "models/zombie.gltf",
+    armor: {
+        chest: ["body"],
+        feet: ["left_leg"],
+    },
+)"#;
+
+#[test]
+fn armor_textures_follow_the_item_material() {
+    assert_eq!(
+        armor_texture_path(
+            Item::Named("diamond_chestplate"),
+            EquipmentSlot::Chest
+        )
+        .as_deref(),
+        Some("images/entity/armor/diamond_layer_1.png")
+    );
+    assert_eq!(
+        armor_texture_path(Item::Named("iron_leggings"), EquipmentSlot::Legs)
+            .as_deref(),
+        Some("images/entity/armor/iron_layer_2.png")
+    );
+    // Worn in the wrong slot, or not armor at all
+    assert_eq!(
+        armor_texture_path(Item::Named("diamond_helmet"), EquipmentSlot::Feet),
+        None
+    );
+    assert_eq!(
+        armor_texture_path(Item::Named("bow_standby"), EquipmentSlot::MainHand),
+        None
+    );
+}
+
+#[test]
+fn equipment_slots_hold_one_item_each() {
+    let mut equipment = Equipment::default()
+        .with(EquipmentSlot::MainHand, "bow_standby")
+        .with(EquipmentSlot::Head, "diamond_helmet");
+    equipment.set(EquipmentSlot::MainHand, "diamond_sword");
+
+    assert_eq!(
+        equipment.get(EquipmentSlot::MainHand),
+        Some(Item::Named("diamond_sword"))
+    );
+    assert_eq!(
+        equipment.clear(EquipmentSlot::Head),
+        Some(Item::Named("diamond_helmet"))
+    );
+    assert_eq!(equipment.get(EquipmentSlot::Head), None);
+    assert_eq!(EquipmentSlot::OffHand.hand_joint(), Some("left_hand"));
+}
+
+#[test]
+fn manifest_reads_armor_bones() {
+    let manifest = ModelManifest::parse(
+        "test.model.ron",
+        r#"(
+            entity_type: "zombie",
+            model: "models/zombie.gltf",
+            armor: {
+                head: ["head"],
+                feet: ["left_leg", "right_leg"],
+            },
+        )"#,
+    )
+    .unwrap();
+
+    assert_eq!(manifest.armor[&EquipmentSlot::Head], ["head"]);
+    assert_eq!(manifest.armor[&EquipmentSlot::Feet].len(), 2);
+    assert!(!manifest.armor.contains_key(&EquipmentSlot::Chest));
+}
+
+/// The parts of a spawned zombie scene equipment is put on
+struct Zombie {
+    entity: Entity,
+    /// Mesh below the `body` bone
+    body_mesh: Entity,
+    /// Bone that is its own mesh
+    left_leg: Entity,
+    right_hand: Entity,
+    left_hand: Entity,
+}
+
+fn equipment_app() -> (App, Zombie) {
+    let mut app = assembly_app();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+        .init_asset::<Image>()
+        .init_asset::<Mesh>()
+        .init_asset::<StandardMaterial>()
+        .init_resource::<ArmorMaterials>()
+        .add_systems(
+            Update,
+            (equipment::remove_equipment, equipment::update_equipment)
+                .chain()
+                .before(viewer::model::assembly::attach_entities),
+        );
+
+    let manifest = ModelManifest::parse("zombie.model.ron", ZOMBIE).unwrap();
+    let id = app
+        .world_mut()
+        .resource_mut::<Assets<ModelManifest>>()
+        .add(manifest)
+        .id();
+    app.world_mut()
+        .resource_mut::<ModelManifests>()
+        .insert("zombie", id);
+
+    let cube = app
+        .world_mut()
+        .resource_mut::<Assets<Mesh>>()
+        .add(Cuboid::new(0.5, 0.75, 0.25));
+    let (entity, right_hand) =
+        spawn_with_joint(&mut app, "right_hand", Transform::default());
+    let world = app.world_mut();
+    let left_hand = world.spawn(ChildOf(entity)).id();
+    let body = world.spawn((Name::new("body"), ChildOf(entity))).id();
+    let body_mesh = world.spawn((Mesh3d(cube.clone()), ChildOf(body))).id();
+    let left_leg = world
+        .spawn((Name::new("left_leg"), Mesh3d(cube), ChildOf(entity)))
+        .id();
+    let mut model = world.entity_mut(entity);
+    model.insert(Model::new("zombie"));
+    model
+        .get_mut::<Joints>()
+        .unwrap()
+        .0
+        .insert("left_hand".to_string(), (left_hand, Transform::default()));
+
+    (
+        app,
+        Zombie {
+            entity,
+            body_mesh,
+            left_leg,
+            right_hand,
+            left_hand,
+        },
+    )
+}
+
+/// Equipment pieces spawned as children of `parent`
+fn pieces_on(app: &mut App, parent: Entity) -> Vec<Entity> {
+    app.world_mut()
+        .query_filtered::<(Entity, &ChildOf), With<EquipmentPiece>>()
+        .iter(app.world())
+        .filter(|(_, child_of)| child_of.parent() == parent)
+        .map(|(piece, _)| piece)
+        .collect()
+}
+
+#[test]
+fn armor_covers_the_listed_bones_and_items_go_in_hand() {
+    let (mut app, zombie) = equipment_app();
+    app.world_mut().entity_mut(zombie.entity).insert(
+        Equipment::default()
+            .with(EquipmentSlot::Chest, "diamond_chestplate")
+            .with(EquipmentSlot::Feet, "diamond_boots")
+            .with(EquipmentSlot::MainHand, "diamond_sword")
+            .with(EquipmentSlot::OffHand, "bow_standby"),
+    );
+    app.update();
+
+    let chest = pieces_on(&mut app, zombie.body_mesh);
+    let feet = pieces_on(&mut app, zombie.left_leg);
+    assert_eq!((chest.len(), feet.len()), (1, 1));
+    // Both use the first layer, so they share one material
+    let material = |piece: Entity| {
+        app.world()
+            .get::<MeshMaterial3d<StandardMaterial>>(piece)
+            .unwrap()
+            .0
+            .clone()
+    };
+    assert_eq!(material(chest[0]), material(feet[0]));
+    assert_eq!(app.world().resource::<ArmorMaterials>().len(), 1);
+
+    for hand in [zombie.right_hand, zombie.left_hand] {
+        let held = pieces_on(&mut app, hand);
+        assert_eq!(held.len(), 1);
+        assert_eq!(app.world().get::<Attached>(held[0]).unwrap().bone, hand);
+    }
+    let spawned = app.world().get::<EquipmentPieces>(zombie.entity).unwrap();
+    assert_eq!(spawned.iter().count(), 4);
+}
+
+#[test]
+fn removing_equipment_despawns_its_pieces() {
+    let (mut app, zombie) = equipment_app();
+    app.world_mut().entity_mut(zombie.entity).insert(
+        Equipment::default()
+            .with(EquipmentSlot::Chest, "diamond_chestplate")
+            .with(EquipmentSlot::MainHand, "diamond_sword"),
+    );
+    app.update();
+    let pieces: Vec<_> = app
+        .world()
+        .get::<EquipmentPieces>(zombie.entity)
+        .unwrap()
+        .iter()
+        .collect();
+    assert_eq!(pieces.len(), 2);
+
+    app.world_mut()
+        .entity_mut(zombie.entity)
+        .remove::<Equipment>();
+    app.update();
+    for piece in pieces {
+        assert!(app.world().get_entity(piece).is_err());
+    }
+    assert!(app.world().get::<EquipmentPieces>(zombie.entity).is_none());
+}
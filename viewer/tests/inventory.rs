@@ -0,0 +1,87 @@
+use viewer::{
+    block::BuiltBlockID,
+    inventory::{Inventory, ItemStack, HOTBAR_SIZE, INVENTORY_SIZE},
+    item::{Item, MAX_STACK_SIZE},
+};
+
+const DIRT: Item = Item::Block(BuiltBlockID::Dirt);
+const APPLE: Item = Item::Named("apple");
+
+#[test]
+fn adding_tops_up_stacks_before_empty_slots() {
+    let mut inventory = Inventory::default();
+    inventory.slots[3] = Some(ItemStack {
+        item: DIRT,
+        count: MAX_STACK_SIZE - 2,
+    });
+    inventory.slots[0] = Some(ItemStack {
+        item: APPLE,
+        count: 1,
+    });
+
+    assert_eq!(inventory.add(DIRT, 5), 0);
+    assert_eq!(
+        inventory.slots[3],
+        Some(ItemStack {
+            item: DIRT,
+            count: MAX_STACK_SIZE
+        })
+    );
+    // The apple's slot is skipped, the rest goes to the first empty one
+    assert_eq!(
+        inventory.slots[1],
+        Some(ItemStack {
+            item: DIRT,
+            count: 3
+        })
+    );
+    assert_eq!(
+        inventory.slots[0],
+        Some(ItemStack {
+            item: APPLE,
+            count: 1
+        })
+    );
+}
+
+#[test]
+fn full_inventory_returns_the_leftover() {
+    let mut inventory = Inventory::default();
+    let capacity = INVENTORY_SIZE as u32 * MAX_STACK_SIZE;
+    assert_eq!(inventory.room_for(DIRT), capacity);
+
+    assert_eq!(inventory.add(DIRT, capacity - 1), 0);
+    assert_eq!(inventory.room_for(DIRT), 1);
+    assert_eq!(inventory.room_for(APPLE), 0);
+
+    assert_eq!(inventory.add(DIRT, 4), 3);
+    assert_eq!(inventory.add(APPLE, 2), 2);
+    assert_eq!(inventory.room_for(DIRT), 0);
+}
+
+#[test]
+fn taking_the_last_item_empties_the_slot() {
+    let mut inventory = Inventory::default();
+    inventory.add(DIRT, 2);
+
+    assert_eq!(inventory.selected_block(), Some(BuiltBlockID::Dirt));
+    assert_eq!(inventory.take_selected(), Some(DIRT));
+    assert_eq!(inventory.take_selected(), Some(DIRT));
+    assert_eq!(inventory.slots[0], None);
+    assert_eq!(inventory.take_selected(), None);
+}
+
+#[test]
+fn selection_wraps_around_the_hotbar() {
+    let mut inventory = Inventory::default();
+
+    inventory.select(HOTBAR_SIZE + 2);
+    assert_eq!(inventory.selected, 2);
+
+    inventory.scroll(-3);
+    assert_eq!(inventory.selected, HOTBAR_SIZE - 1);
+    inventory.scroll(1);
+    assert_eq!(inventory.selected, 0);
+    inventory.scroll(2 * HOTBAR_SIZE as i32 + 4);
+    assert_eq!(inventory.selected, 4);
+}
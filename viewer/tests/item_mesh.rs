@@ -0,0 +1,103 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::{
+        mesh::VertexAttributeValues,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+use viewer::item::{
+    mesh::{build_item_models, extrude_sprite, ItemMeshes, ItemModel},
+    Item,
+};
+
+/// Transparent 16×16 sprite with the given pixels opaque
+fn sprite(opaque: &[(u32, u32)]) -> Image {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: 16,
+            height: 16,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    for &(x, y) in opaque {
+        image.set_color_at(x, y, Color::WHITE).unwrap();
+    }
+    image
+}
+
+fn positions(mesh: &Mesh) -> &[[f32; 3]] {
+    match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+        Some(VertexAttributeValues::Float32x3(positions)) => positions,
+        _ => panic!("mesh has no positions"),
+    }
+}
+
+/// Front, back and side quads of an extruded mesh
+fn quads(mesh: &Mesh) -> usize {
+    positions(mesh).len() / 4
+}
+
+#[test]
+fn lone_pixel_gets_all_four_sides() {
+    let mesh = extrude_sprite(&sprite(&[(3, 5)]));
+    assert_eq!(quads(&mesh), 2 + 4);
+    assert_eq!(mesh.indices().unwrap().len(), 6 * 6);
+}
+
+#[test]
+fn sides_only_where_alpha_changes() {
+    // Two pixels side by side share no face
+    let pair = extrude_sprite(&sprite(&[(3, 5), (4, 5)]));
+    assert_eq!(quads(&pair), 2 + 6);
+
+    // A full sprite only has sides along its edges
+    let all: Vec<_> =
+        (0..16).flat_map(|y| (0..16).map(move |x| (x, y))).collect();
+    let full = extrude_sprite(&sprite(&all));
+    assert_eq!(quads(&full), 2 + 4 * 16);
+}
+
+#[test]
+fn sprite_is_one_block_wide_and_one_pixel_deep() {
+    let mesh = extrude_sprite(&sprite(&[(0, 0), (15, 15)]));
+    let (min, max) = positions(&mesh).iter().fold(
+        (Vec3::MAX, Vec3::MIN),
+        |(min, max), &position| {
+            (min.min(position.into()), max.max(position.into()))
+        },
+    );
+    assert_eq!(min, Vec3::new(-0.5, -0.5, -1.0 / 32.0));
+    assert_eq!(max, Vec3::new(0.5, 0.5, 1.0 / 32.0));
+}
+
+#[test]
+fn meshes_are_cached_per_texture() {
+    let mut app = App::new();
+    app.init_resource::<Assets<Image>>()
+        .init_resource::<Assets<Mesh>>()
+        .init_resource::<Assets<StandardMaterial>>()
+        .init_resource::<ItemMeshes>()
+        .add_systems(Update, build_item_models);
+
+    let texture = app
+        .world_mut()
+        .resource_mut::<Assets<Image>>()
+        .add(sprite(&[(7, 7)]));
+    let model = ItemModel {
+        item: Item::Named("apple"),
+        texture,
+    };
+    let first = app.world_mut().spawn(model.clone()).id();
+    let second = app.world_mut().spawn(model).id();
+    app.update();
+
+    assert_eq!(app.world().resource::<ItemMeshes>().len(), 1);
+    assert_eq!(app.world().resource::<Assets<Mesh>>().len(), 1);
+    let mesh = |entity| app.world().get::<Mesh3d>(entity).unwrap().0.clone();
+    assert_eq!(mesh(first), mesh(second));
+}
@@ -0,0 +1,383 @@
+use std::{cell::RefCell, collections::HashMap, time::Duration};
+
+use bevy::{animation::AnimationTargetId, prelude::*};
+use viewer::{
+    animation::{
+        AnimationConfig, AnimationConfigs, AnimationOwner, BlendWeights,
+        ModelAnimation, PlayAnimation,
+    },
+    model::{
+        animation::{self, ActiveBlends, AnimationAssets},
+        manifest::{Model, ModelManifest},
+        mask::BoneMasks,
+    },
+};
+
+const ZOMBIE: &str = r#"(
+    entity_type: "zombie",
+    model: "models/zombie.gltf",
+    animations: {
+        "walk": (clip: "animation.zombie.walk", repeat: true),
+        "riding": (clip: "riding", paused: true),
+    },
+    blends: {
+        "riding_walk": (animations: [("riding", 1.0), ("walk", 3.0)]),
+    },
+)"#;
+
+const SKELETON: &str = r#"(
+    entity_type: "skeleton",
+    model: "models/skeleton.gltf",
+    animations: {
+        "riding": (clip: "riding", speed: 0.0, paused: true),
+    },
+)"#;
+
+struct Registry {
+    graphs: Assets<AnimationGraph>,
+    clip_assets: Assets<AnimationClip>,
+    animation_assets: AnimationAssets,
+    animation_configs: AnimationConfigs,
+    /// Path each empty clip was made for
+    clips: HashMap<Handle<AnimationClip>, String>,
+}
+
+impl Registry {
+    fn new() -> Self {
+        Self {
+            graphs: Assets::default(),
+            clip_assets: Assets::default(),
+            animation_assets: AnimationAssets::new(),
+            animation_configs: AnimationConfigs::default(),
+            clips: HashMap::new(),
+        }
+    }
+
+    /// Register a manifest with a distinct clip handle for every path
+    fn register(&mut self, text: &str) {
+        let manifest = ModelManifest::parse("test.model.ron", text).unwrap();
+        let clips = RefCell::new((&mut self.clip_assets, &mut self.clips));
+        let resolve = |path: &str| {
+            let (clip_assets, clips) = &mut *clips.borrow_mut();
+            let handle = clip_assets.add(AnimationClip::default());
+            clips.insert(handle.clone(), path.to_string());
+            Some(handle)
+        };
+        manifest.register(
+            &resolve,
+            &mut self.animation_assets,
+            &mut self.animation_configs,
+        );
+        self.animation_assets.finalize_graphs(&mut self.graphs);
+    }
+
+    fn clip_path(&self, entity_type: &str, name: &str) -> &str {
+        let clip = self
+            .animation_assets
+            .model(entity_type)
+            .and_then(|animations| animations.clip(name))
+            .unwrap();
+        &self.clips[clip]
+    }
+}
+
+fn speed(config: Option<&AnimationConfig>) -> f32 {
+    match config {
+        Some(AnimationConfig::Single { speed, .. }) => *speed,
+        other => panic!("expected a single animation, got {:?}", other),
+    }
+}
+
+#[test]
+fn two_models_can_both_define_riding() {
+    let mut registry = Registry::new();
+    registry.register(ZOMBIE);
+    registry.register(SKELETON);
+
+    assert_eq!(
+        registry.clip_path("zombie", "riding"),
+        "models/zombie.gltf@riding"
+    );
+    assert_eq!(
+        registry.clip_path("skeleton", "riding"),
+        "models/skeleton.gltf@riding"
+    );
+
+    let configs = &registry.animation_configs;
+    assert_eq!(speed(configs.get("zombie", "riding")), 1.0);
+    assert_eq!(speed(configs.get("skeleton", "riding")), 0.0);
+    assert!(configs.get("skeleton", "walk").is_none());
+}
+
+#[test]
+fn each_model_gets_its_own_graph() {
+    let mut registry = Registry::new();
+    registry.register(ZOMBIE);
+    registry.register(SKELETON);
+
+    let zombie = registry.animation_assets.model("zombie").unwrap();
+    let skeleton = registry.animation_assets.model("skeleton").unwrap();
+    assert_ne!(zombie.graph, skeleton.graph);
+    assert!(zombie.nodes.contains_key("riding"));
+    assert!(skeleton.nodes.contains_key("riding"));
+    assert_eq!(registry.graphs.len(), 2);
+}
+
+#[test]
+fn registering_again_only_replaces_that_model() {
+    let mut registry = Registry::new();
+    registry.register(ZOMBIE);
+    registry.register(SKELETON);
+    registry.register(&SKELETON.replace("speed: 0.0", "speed: 2.0"));
+
+    let configs = &registry.animation_configs;
+    assert_eq!(speed(configs.get("skeleton", "riding")), 2.0);
+    assert_eq!(speed(configs.get("zombie", "riding")), 1.0);
+    assert_eq!(
+        registry.clip_path("zombie", "riding"),
+        "models/zombie.gltf@riding"
+    );
+}
+
+#[test]
+fn blends_share_the_model_graph() {
+    let mut registry = Registry::new();
+    registry.register(ZOMBIE);
+
+    let zombie = registry.animation_assets.model("zombie").unwrap();
+    let blend = &zombie.blends["riding_walk"];
+    assert_eq!(blend.clips.len(), 2);
+    assert!(blend
+        .clips
+        .iter()
+        .all(|(_, node)| !zombie.nodes.values().any(|n| n == node)));
+    assert_eq!(registry.graphs.len(), 1);
+}
+
+/// A zombie whose scene has one animation player playing `walk`
+fn app_with_zombie() -> (App, Entity, Entity) {
+    let mut registry = Registry::new();
+    registry.register(ZOMBIE);
+
+    let mut app = App::new();
+    app.add_event::<PlayAnimation>()
+        .insert_resource(registry.animation_assets)
+        .insert_resource(registry.animation_configs)
+        .add_systems(
+            Update,
+            (animation::switch_animations, animation::sync_blend_weights)
+                .chain(),
+        );
+
+    let root = app
+        .world_mut()
+        .spawn((Model::new("zombie"), ModelAnimation::new("walk")))
+        .id();
+    let player = app
+        .world_mut()
+        .spawn((
+            AnimationPlayer::default(),
+            AnimationTransitions::new(),
+            ActiveBlends::default(),
+            ModelAnimation::new("walk"),
+            AnimationOwner("zombie".to_string()),
+            ChildOf(root),
+        ))
+        .id();
+    app.update();
+
+    (app, root, player)
+}
+
+fn main_animation(app: &App, player: Entity) -> Option<AnimationNodeIndex> {
+    app.world()
+        .get::<AnimationTransitions>(player)
+        .unwrap()
+        .get_main_animation()
+}
+
+#[test]
+fn changing_model_animation_switches_the_player() {
+    let (mut app, root, player) = app_with_zombie();
+
+    app.world_mut().get_mut::<ModelAnimation>(root).unwrap().0 =
+        "riding".to_string();
+    app.update();
+
+    let riding = app
+        .world()
+        .resource::<AnimationAssets>()
+        .model("zombie")
+        .unwrap()
+        .nodes["riding"];
+    assert_eq!(main_animation(&app, player), Some(riding));
+    assert_eq!(
+        app.world().get::<ModelAnimation>(player),
+        Some(&ModelAnimation::new("riding"))
+    );
+}
+
+#[test]
+fn play_animation_event_fades_to_a_blend() {
+    let (mut app, root, player) = app_with_zombie();
+
+    app.world_mut().send_event(PlayAnimation {
+        entity: root,
+        name: "riding_walk".to_string(),
+        fade: Duration::from_millis(100),
+    });
+    app.update();
+
+    let blend = app
+        .world()
+        .resource::<AnimationAssets>()
+        .model("zombie")
+        .unwrap()
+        .blends["riding_walk"]
+        .clone();
+    assert_eq!(main_animation(&app, player), Some(blend.fade));
+    let animation_player = app.world().get::<AnimationPlayer>(player).unwrap();
+    assert!(blend
+        .clips
+        .iter()
+        .all(|(_, node)| animation_player.is_playing_animation(*node)));
+    assert_eq!(
+        app.world().get::<ModelAnimation>(root),
+        Some(&ModelAnimation::new("riding_walk"))
+    );
+}
+
+fn clip_weights(app: &App, player: Entity, blend: &str) -> Vec<(String, f32)> {
+    let blend = &app
+        .world()
+        .resource::<AnimationAssets>()
+        .model("zombie")
+        .unwrap()
+        .blends[blend];
+    let animation_player = app.world().get::<AnimationPlayer>(player).unwrap();
+    blend
+        .clips
+        .iter()
+        .map(|(name, node)| {
+            let weight = animation_player.animation(*node).unwrap().weight();
+            (name.clone(), weight)
+        })
+        .collect()
+}
+
+#[test]
+fn blend_weights_are_honored_and_adjustable() {
+    let (mut app, root, player) = app_with_zombie();
+
+    app.world_mut().get_mut::<ModelAnimation>(root).unwrap().0 =
+        "riding_walk".to_string();
+    app.update();
+    assert_eq!(
+        clip_weights(&app, player, "riding_walk"),
+        [("riding".to_string(), 0.25), ("walk".to_string(), 0.75)]
+    );
+
+    let mut weights = BlendWeights::default();
+    weights.set("walk", 1.0);
+    app.world_mut().entity_mut(root).insert(weights);
+    app.update();
+    assert_eq!(
+        clip_weights(&app, player, "riding_walk"),
+        [("riding".to_string(), 0.5), ("walk".to_string(), 0.5)]
+    );
+}
+
+fn path(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+fn target(names: &[&str]) -> AnimationTargetId {
+    let names: Vec<Name> = names
+        .iter()
+        .map(|&name| Name::new(name.to_string()))
+        .collect();
+    AnimationTargetId::from_names(names.iter())
+}
+
+#[test]
+fn masks_claim_bones_by_nearest_listed_ancestor() {
+    let masks = HashMap::from([
+        ("arms".to_string(), path(&["left_arm", "right_arm"])),
+        ("body".to_string(), path(&["body", "missing_bone"])),
+    ]);
+    let masks = BoneMasks::from_paths(
+        [
+            path(&["root"]),
+            path(&["root", "body"]),
+            path(&["root", "body", "head"]),
+            path(&["root", "body", "left_arm"]),
+            path(&["root", "body", "left_arm", "hand"]),
+        ],
+        &masks,
+    );
+    assert_eq!(masks.missing, ["missing_bone", "right_arm"]);
+
+    let mut graph = AnimationGraph::new();
+    masks.apply(&mut graph);
+    let arms = 1 << masks.group("arms").unwrap();
+    let body = 1 << masks.group("body").unwrap();
+    let rest = 0b100;
+    let group_of = |names: &[&str]| graph.mask_groups[&target(names)];
+    assert_eq!(group_of(&["root"]), rest);
+    assert_eq!(group_of(&["root", "body", "head"]), body);
+    assert_eq!(group_of(&["root", "body", "left_arm", "hand"]), arms);
+
+    // Arms only: every other group is masked out
+    assert_eq!(masks.only(Some("arms")), body | rest);
+    assert_eq!(masks.only(None), 0);
+}
+
+#[test]
+fn animations_added_after_the_scene_start_and_stop_on_removal() {
+    let mut registry = Registry::new();
+    registry.register(ZOMBIE);
+
+    let mut app = App::new();
+    app.insert_resource(registry.animation_assets)
+        .insert_resource(registry.animation_configs)
+        .add_systems(
+            Update,
+            (
+                animation::setup_added_animations,
+                animation::stop_removed_animations,
+                animation::update_animations,
+            )
+                .chain(),
+        );
+
+    // The scene is ready, but the model has nothing to play yet
+    let root = app.world_mut().spawn(Model::new("zombie")).id();
+    let player = app
+        .world_mut()
+        .spawn((AnimationPlayer::default(), ChildOf(root)))
+        .id();
+    app.update();
+    assert!(app.world().get::<ModelAnimation>(player).is_none());
+
+    app.world_mut()
+        .entity_mut(root)
+        .insert(ModelAnimation::new("walk"));
+    app.update();
+    let walk = app
+        .world()
+        .resource::<AnimationAssets>()
+        .model("zombie")
+        .unwrap()
+        .nodes["walk"];
+    assert_eq!(main_animation(&app, player), Some(walk));
+
+    app.world_mut().entity_mut(root).remove::<ModelAnimation>();
+    app.update();
+    let player = app.world().entity(player);
+    assert!(!player
+        .get::<AnimationPlayer>()
+        .unwrap()
+        .is_playing_animation(walk));
+    assert!(!player.contains::<ModelAnimation>());
+    assert!(!player.contains::<AnimationTransitions>());
+}
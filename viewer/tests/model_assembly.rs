@@ -0,0 +1,71 @@
+mod common;
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use common::{assembly_app, spawn_with_joint};
+use viewer::model::assembly::{
+    AttachEntity, Attached, Attachment, DetachEntity, Joints,
+};
+
+/// A carrier with a `head` joint on a bone entity
+fn spawn_carrier(app: &mut App) -> (Entity, Entity) {
+    spawn_with_joint(app, "head", Transform::from_xyz(0.0, 0.5, 0.0))
+}
+
+#[test]
+fn attachment_waits_for_the_carrier_joints() {
+    let mut app = assembly_app();
+    let carrier = app.world_mut().spawn(Transform::default()).id();
+    let hat = app.world_mut().spawn(Attachment::new(carrier, "head")).id();
+    app.update();
+    assert!(app.world().get::<Attached>(hat).is_none());
+
+    let bone = app.world_mut().spawn(ChildOf(carrier)).id();
+    app.world_mut()
+        .entity_mut(carrier)
+        .insert(Joints(HashMap::from([(
+            "head".to_string(),
+            (bone, Transform::from_xyz(0.0, 0.5, 0.0)),
+        )])));
+    app.update();
+
+    assert_eq!(app.world().get::<ChildOf>(hat).unwrap().parent(), bone);
+    assert_eq!(
+        app.world().get::<Transform>(hat).unwrap().translation,
+        Vec3::new(0.0, 0.5, 0.0)
+    );
+}
+
+#[test]
+fn events_attach_and_detach() {
+    let mut app = assembly_app();
+    let (carrier, bone) = spawn_carrier(&mut app);
+    let hat = app.world_mut().spawn(Transform::default()).id();
+
+    app.world_mut().send_event(AttachEntity {
+        entity: hat,
+        attachment: Attachment::new(carrier, "head"),
+    });
+    app.update();
+    assert_eq!(app.world().get::<Attached>(hat).unwrap().bone, bone);
+
+    app.world_mut().send_event(DetachEntity { entity: hat });
+    app.update();
+    assert!(app.world().get::<Attachment>(hat).is_none());
+    assert!(app.world().get::<ChildOf>(hat).is_none());
+}
+
+#[test]
+fn unknown_joint_drops_the_attachment() {
+    let mut app = assembly_app();
+    let (carrier, _) = spawn_carrier(&mut app);
+    let hat = app
+        .world_mut()
+        .spawn(Attachment::new(carrier, "saddle"))
+        .id();
+    app.update();
+
+    assert!(app.world().get::<Attachment>(hat).is_none());
+    assert!(app.world().get::<ChildOf>(hat).is_none());
+}
@@ -0,0 +1,111 @@
+use std::f32::consts::FRAC_PI_4;
+
+use bevy::{ecs::system::RunSystemOnce, prelude::*};
+use viewer::model::{
+    manifest::ModelManifest,
+    procedural::{
+        apply_procedural_bones, look_angles, reset_procedural_bones, HeadBone,
+        LimbBone, LimbSwing, ProceduralOffset,
+    },
+};
+
+fn assert_near(actual: f32, expected: f32) {
+    assert!(
+        (actual - expected).abs() < 1e-5,
+        "expected {expected}, got {actual}"
+    );
+}
+
+#[test]
+fn look_angles_turn_forward_towards_the_target() {
+    let (yaw, pitch) = look_angles(Vec3::NEG_Z);
+    assert_near(yaw, 0.0);
+    assert_near(pitch, 0.0);
+
+    // Left of a model facing -Z is -X, turning left is a positive yaw
+    let (yaw, pitch) = look_angles(Vec3::new(-1.0, 0.0, -1.0));
+    assert_near(yaw, FRAC_PI_4);
+    assert_near(pitch, 0.0);
+
+    let (yaw, pitch) = look_angles(Vec3::new(0.0, 1.0, -1.0));
+    assert_near(yaw, 0.0);
+    assert_near(pitch, FRAC_PI_4);
+}
+
+#[test]
+fn limbs_swing_in_opposite_phase() {
+    let left = LimbBone {
+        model: Entity::PLACEHOLDER,
+        phase: 0.0,
+        amplitude: 1.0,
+    };
+    let right = LimbBone {
+        phase: 180f32.to_radians(),
+        ..left.clone()
+    };
+
+    let standing = LimbSwing::default();
+    assert_near(left.angle(&standing), 0.0);
+
+    let mut walking = LimbSwing::default();
+    walking.amount = 0.5;
+    assert_near(left.angle(&walking), 0.5);
+    assert_near(right.angle(&walking), -0.5);
+}
+
+#[test]
+fn manifest_reads_head_and_limbs() {
+    let manifest = ModelManifest::parse(
+        "test.model.ron",
+        r#"(
+            entity_type: "zombie",
+            model: "models/zombie.gltf",
+            head: Some((bone: "head", max_yaw: 60.0)),
+            limbs: [(bone: "left_leg", phase: 180.0), (bone: "right_leg")],
+        )"#,
+    )
+    .unwrap();
+
+    let head = manifest.head.unwrap();
+    assert_eq!(head.bone, "head");
+    assert_eq!(head.max_yaw, 60.0);
+    assert_eq!(head.max_pitch, 40.0);
+    assert_eq!(manifest.limbs.len(), 2);
+    assert_eq!(manifest.limbs[1].phase, 0.0);
+    assert_eq!(manifest.limbs[1].amplitude, 80.0);
+}
+
+#[test]
+fn head_turns_in_parent_space_and_resets() {
+    let mut world = World::new();
+    // Pose left by the animation, leaning sideways
+    let pose = Quat::from_rotation_z(0.6);
+    let head = world
+        .spawn((
+            Transform::from_rotation(pose),
+            HeadBone {
+                model: Entity::PLACEHOLDER,
+                max_yaw: 1.0,
+                max_pitch: 1.0,
+                yaw: 0.5,
+                pitch: 0.3,
+            },
+            ProceduralOffset::default(),
+        ))
+        .id();
+    let rotation =
+        |world: &World| world.get::<Transform>(head).unwrap().rotation;
+
+    world.run_system_once(apply_procedural_bones).unwrap();
+    let turn = Quat::from_rotation_y(0.5) * Quat::from_rotation_x(0.3);
+    assert!(rotation(&world).abs_diff_eq(turn * pose, 1e-5));
+
+    world.run_system_once(reset_procedural_bones).unwrap();
+    assert!(rotation(&world).abs_diff_eq(pose, 1e-5));
+
+    // Applying again doesn't stack on last frame's turn
+    world.run_system_once(apply_procedural_bones).unwrap();
+    world.run_system_once(reset_procedural_bones).unwrap();
+    world.run_system_once(apply_procedural_bones).unwrap();
+    assert!(rotation(&world).abs_diff_eq(turn * pose, 1e-5));
+}
@@ -0,0 +1,294 @@
+mod common;
+
+use std::{f32::consts::FRAC_PI_2, time::Duration};
+
+use bevy::prelude::*;
+use common::{assembly_app, spawn_with_joint};
+use viewer::{
+    animation::{ModelAnimation, PlayAnimation},
+    entity::riding::{
+        self, PendingRidingPose, Riders, Rides, RidingPose, SEAT_JOINT,
+    },
+    model::{
+        assembly::{self, Attached},
+        manifest::{Model, ModelManifest, ModelManifests},
+    },
+    simple_control::Player,
+};
+
+const SKELETON: &str = r#"(
+    entity_type: "skeleton",
+    model: "models/skeleton.gltf",
+    riding_animation: Some("riding"),
+)"#;
+
+fn app() -> App {
+    let mut app = assembly_app();
+    app.add_event::<PlayAnimation>().add_systems(
+        Update,
+        (
+            riding::mount_riders,
+            riding::pose_riders,
+            riding::dismount_riders,
+        )
+            .chain()
+            .before(assembly::attach_entities),
+    );
+    app
+}
+
+/// An entity with a seat on a bone, returns the entity and the bone
+fn spawn_seated(app: &mut App) -> (Entity, Entity) {
+    spawn_with_joint(app, SEAT_JOINT, Transform::from_xyz(0.0, 0.875, 0.0))
+}
+
+/// Load and register a manifest, as if it was read from the assets folder
+fn register(app: &mut App, text: &str) {
+    let manifest = ModelManifest::parse("test.model.ron", text).unwrap();
+    let entity_type = manifest.entity_type.clone();
+    let id = app
+        .world_mut()
+        .resource_mut::<Assets<ModelManifest>>()
+        .add(manifest)
+        .id();
+    app.world_mut()
+        .resource_mut::<ModelManifests>()
+        .insert(&entity_type, id);
+}
+
+fn played(app: &App) -> Vec<(Entity, String)> {
+    app.world()
+        .resource::<Events<PlayAnimation>>()
+        .iter_current_update_events()
+        .map(|event| (event.entity, event.name.clone()))
+        .collect()
+}
+
+fn player() -> Player {
+    Player {
+        speed: 5.0,
+        sensitivity: 0.003,
+        yaw: 0.0,
+        pitch: 0.0,
+        distance_walked: 0.0,
+    }
+}
+
+#[test]
+fn riders_sit_on_the_seat_and_stack() {
+    let mut app = app();
+    let (pig, pig_seat) = spawn_seated(&mut app);
+    let (skeleton, skeleton_seat) = spawn_seated(&mut app);
+    app.world_mut().entity_mut(skeleton).insert(Rides(pig));
+    let zombie = app.world_mut().spawn(Rides(skeleton)).id();
+    app.update();
+
+    assert_eq!(
+        app.world().get::<Attached>(skeleton).unwrap().bone,
+        pig_seat
+    );
+    assert_eq!(
+        app.world().get::<ChildOf>(zombie).unwrap().parent(),
+        skeleton_seat
+    );
+    let riders = app.world().get::<Riders>(pig).unwrap();
+    assert_eq!(riders.iter().collect::<Vec<_>>(), [skeleton]);
+}
+
+#[test]
+fn removing_rides_dismounts() {
+    let mut app = app();
+    let (pig, _) = spawn_seated(&mut app);
+    let skeleton = app.world_mut().spawn(Rides(pig)).id();
+    app.update();
+
+    app.world_mut().entity_mut(skeleton).remove::<Rides>();
+    app.update();
+
+    assert!(app.world().get::<ChildOf>(skeleton).is_none());
+    assert!(app.world().get::<Attached>(skeleton).is_none());
+    assert!(app.world().get::<Riders>(pig).is_none());
+}
+
+#[test]
+fn riding_pose_waits_for_the_manifest_and_is_undone() {
+    let mut app = app();
+    let (pig, _) = spawn_seated(&mut app);
+    let skeleton = app
+        .world_mut()
+        .spawn((
+            Model::new("skeleton"),
+            ModelAnimation::new("walk"),
+            Rides(pig),
+        ))
+        .id();
+    app.update();
+    assert!(app.world().get::<PendingRidingPose>(skeleton).is_some());
+    assert!(played(&app).is_empty());
+
+    register(&mut app, SKELETON);
+    app.update();
+    assert!(app.world().get::<PendingRidingPose>(skeleton).is_none());
+    assert_eq!(
+        app.world()
+            .get::<RidingPose>(skeleton)
+            .unwrap()
+            .previous
+            .as_deref(),
+        Some("walk")
+    );
+    assert_eq!(played(&app), [(skeleton, "riding".to_string())]);
+
+    app.world_mut().entity_mut(skeleton).remove::<Rides>();
+    app.update();
+    assert!(app.world().get::<RidingPose>(skeleton).is_none());
+    assert_eq!(played(&app), [(skeleton, "walk".to_string())]);
+}
+
+#[test]
+fn riders_without_an_animation_go_back_to_none() {
+    let mut app = app();
+    register(&mut app, SKELETON);
+    let (pig, _) = spawn_seated(&mut app);
+    let skeleton = app
+        .world_mut()
+        .spawn((Model::new("skeleton"), Rides(pig)))
+        .id();
+    app.update();
+    assert_eq!(
+        app.world().get::<ModelAnimation>(skeleton),
+        Some(&ModelAnimation::new("riding"))
+    );
+
+    app.world_mut().entity_mut(skeleton).remove::<Rides>();
+    app.update();
+    assert!(app.world().get::<ModelAnimation>(skeleton).is_none());
+    assert!(app.world().get::<RidingPose>(skeleton).is_none());
+    assert!(played(&app).is_empty());
+}
+
+#[test]
+fn dismounted_riders_fall_back_to_the_default_animation() {
+    let mut app = app();
+    register(
+        &mut app,
+        &SKELETON.replace(
+            "riding_animation",
+            "default_animation: Some(\"idle\"), riding_animation",
+        ),
+    );
+    let (pig, _) = spawn_seated(&mut app);
+    let skeleton = app
+        .world_mut()
+        .spawn((Model::new("skeleton"), Rides(pig)))
+        .id();
+    app.update();
+
+    app.world_mut().entity_mut(skeleton).remove::<Rides>();
+    app.update();
+    assert_eq!(played(&app), [(skeleton, "idle".to_string())]);
+}
+
+#[test]
+fn riders_without_a_riding_animation_stop_waiting() {
+    let mut app = app();
+    register(
+        &mut app,
+        r#"(entity_type: "pig", model: "models/pig.gltf")"#,
+    );
+    let (mount, _) = spawn_seated(&mut app);
+    let pig = app
+        .world_mut()
+        .spawn((Model::new("pig"), Rides(mount)))
+        .id();
+    let marker = app.world_mut().spawn(Rides(mount)).id();
+    app.update();
+
+    for rider in [pig, marker] {
+        assert!(app.world().get::<PendingRidingPose>(rider).is_none());
+        assert!(app.world().get::<RidingPose>(rider).is_none());
+    }
+    assert!(played(&app).is_empty());
+}
+
+#[test]
+fn player_mounts_the_nearest_free_seat() {
+    let mut app = App::new();
+    app.init_resource::<ButtonInput<KeyCode>>()
+        .add_systems(Update, riding::mount_nearest);
+    let mount_at = |app: &mut App, x: f32| {
+        let (mount, _) = spawn_seated(app);
+        app.world_mut()
+            .entity_mut(mount)
+            .insert(GlobalTransform::from_xyz(x, 0.0, 0.0));
+        mount
+    };
+    let taken = mount_at(&mut app, 0.5);
+    let near = mount_at(&mut app, 1.5);
+    mount_at(&mut app, -2.0);
+    mount_at(&mut app, 5.0);
+    app.world_mut().spawn(Rides(taken));
+
+    let player = app
+        .world_mut()
+        .spawn((player(), Transform::from_xyz(0.0, 1.62, 0.0)))
+        .id();
+    app.update();
+    assert!(app.world().get::<Rides>(player).is_none());
+
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(KeyCode::KeyR);
+    app.update();
+    assert_eq!(app.world().get::<Rides>(player), Some(&Rides(near)));
+}
+
+#[test]
+fn player_steers_the_bottom_mount() {
+    let mut app = App::new();
+    app.init_resource::<Time>()
+        .init_resource::<ButtonInput<KeyCode>>()
+        .add_systems(Update, riding::drive_mounts);
+    let pig = app.world_mut().spawn(Transform::default()).id();
+    let skeleton = app
+        .world_mut()
+        .spawn((Transform::default(), Rides(pig)))
+        .id();
+    let player = app
+        .world_mut()
+        .spawn((
+            Player {
+                yaw: FRAC_PI_2,
+                ..player()
+            },
+            Rides(skeleton),
+        ))
+        .id();
+
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(KeyCode::KeyW);
+    app.world_mut()
+        .resource_mut::<Time>()
+        .advance_by(Duration::from_millis(500));
+    app.update();
+
+    // Facing -X, half a second at four blocks per second
+    let pig_transform = app.world().get::<Transform>(pig).unwrap();
+    assert!(pig_transform
+        .translation
+        .abs_diff_eq(Vec3::new(-2.0, 0.0, 0.0), 1e-5));
+    assert!(pig_transform
+        .rotation
+        .abs_diff_eq(Quat::from_rotation_y(FRAC_PI_2), 1e-5));
+    assert_eq!(
+        app.world().get::<Transform>(skeleton),
+        Some(&Transform::default())
+    );
+
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(KeyCode::ShiftLeft);
+    app.update();
+    assert!(app.world().get::<Rides>(player).is_none());
+}
@@ -0,0 +1,296 @@
+use bevy::math::UVec3;
+use viewer::{
+    block::BuiltBlockID,
+    schematic::{
+        sponge, Mirror, Placement, Rotation, Schematic, SchematicError,
+    },
+};
+
+/// Box of `size` cycling through `palette`
+fn schematic(size: UVec3, palette: &[BuiltBlockID]) -> Schematic {
+    let count = (size.x * size.y * size.z) as usize;
+    Schematic {
+        size,
+        palette: palette.to_vec(),
+        blocks: (0..count).map(|i| (i % palette.len()) as u16).collect(),
+    }
+}
+
+#[test]
+fn native_format_round_trips() {
+    // Five entries need three bits, so blocks straddle packed words
+    let original = schematic(
+        UVec3::new(5, 3, 7),
+        &[
+            BuiltBlockID::Air,
+            BuiltBlockID::Brick,
+            BuiltBlockID::Dirt,
+            BuiltBlockID::Grass,
+            BuiltBlockID::PlanksOak,
+        ],
+    );
+    let mut bytes = Vec::new();
+    original.write_to(&mut bytes).unwrap();
+
+    let read = Schematic::read_from(bytes.as_slice()).unwrap();
+    assert_eq!(read, original);
+}
+
+#[test]
+fn single_block_palette_round_trips() {
+    let original = schematic(UVec3::new(2, 2, 2), &[BuiltBlockID::Dirt]);
+    let mut bytes = Vec::new();
+    original.write_to(&mut bytes).unwrap();
+
+    assert_eq!(Schematic::read_from(bytes.as_slice()).unwrap(), original);
+}
+
+#[test]
+fn native_format_rejects_other_files() {
+    let result = Schematic::read_from(&b"PNG\0\x01"[..]);
+    assert!(matches!(result, Err(SchematicError::BadMagic)));
+}
+
+/// The native header up to the block data: magic, version, size, a one
+/// entry palette and bits per block
+fn native_header(size: [u32; 3]) -> Vec<u8> {
+    let mut file = b"VSCH\x01".to_vec();
+    for side in size {
+        file.extend(side.to_le_bytes());
+    }
+    file.extend(1u16.to_le_bytes());
+    file.push(3);
+    file.extend(b"air");
+    file.push(1);
+    file
+}
+
+#[test]
+fn native_format_refuses_oversized_schematics() {
+    for size in [[u32::MAX; 3], [257, 256, 256]] {
+        let result = Schematic::read_from(native_header(size).as_slice());
+        assert!(matches!(result, Err(SchematicError::Malformed("size"))));
+    }
+}
+
+#[test]
+fn native_format_rejects_truncated_block_data() {
+    // Within the limit, but the words aren't there
+    let result = Schematic::read_from(native_header([256; 3]).as_slice());
+    assert!(matches!(result, Err(SchematicError::Io(_))));
+}
+
+/// Uncompressed NBT, just enough of it to write Sponge schematics
+mod nbt {
+    pub fn name(out: &mut Vec<u8>, id: u8, name: &str) {
+        out.push(id);
+        out.extend((name.len() as u16).to_be_bytes());
+        out.extend(name.as_bytes());
+    }
+
+    pub fn int(out: &mut Vec<u8>, key: &str, value: i32) {
+        name(out, 3, key);
+        out.extend(value.to_be_bytes());
+    }
+
+    pub fn short(out: &mut Vec<u8>, key: &str, value: i16) {
+        name(out, 2, key);
+        out.extend(value.to_be_bytes());
+    }
+
+    pub fn bytes(out: &mut Vec<u8>, key: &str, value: &[u8]) {
+        name(out, 7, key);
+        out.extend((value.len() as i32).to_be_bytes());
+        out.extend(value);
+    }
+
+    pub fn palette(out: &mut Vec<u8>, entries: &[(&str, i32)]) {
+        name(out, 10, "Palette");
+        for &(id, index) in entries {
+            int(out, id, index);
+        }
+        out.push(0);
+    }
+}
+
+const PALETTE: &[(&str, i32)] = &[
+    ("minecraft:air", 0),
+    ("minecraft:bricks", 1),
+    ("minecraft:grass_block[snowy=false]", 2),
+    ("minecraft:stone", 3),
+];
+/// A 2×1×2 box: air, bricks, grass, stone
+const BLOCK_DATA: &[u8] = &[0, 1, 2, 3];
+
+fn sponge_header(out: &mut Vec<u8>, version: i32) {
+    nbt::int(out, "Version", version);
+    nbt::short(out, "Width", 2);
+    nbt::short(out, "Height", 1);
+    nbt::short(out, "Length", 2);
+}
+
+fn expected_blocks(schematic: &Schematic) {
+    let blocks: Vec<_> = schematic.iter().map(|(_, block)| block).collect();
+    assert_eq!(schematic.size, UVec3::new(2, 1, 2));
+    // Stone isn't supported and becomes air
+    assert_eq!(
+        blocks,
+        [
+            BuiltBlockID::Air,
+            BuiltBlockID::Brick,
+            BuiltBlockID::Grass,
+            BuiltBlockID::Air,
+        ]
+    );
+}
+
+#[test]
+fn sponge_v2_is_read() {
+    let mut file = Vec::new();
+    nbt::name(&mut file, 10, "Schematic");
+    sponge_header(&mut file, 2);
+    nbt::palette(&mut file, PALETTE);
+    nbt::bytes(&mut file, "BlockData", BLOCK_DATA);
+    file.push(0);
+
+    expected_blocks(&sponge::read(file.as_slice()).unwrap());
+}
+
+#[test]
+fn sponge_v3_is_read() {
+    let mut file = Vec::new();
+    nbt::name(&mut file, 10, "");
+    nbt::name(&mut file, 10, "Schematic");
+    sponge_header(&mut file, 3);
+    nbt::name(&mut file, 10, "Blocks");
+    nbt::palette(&mut file, PALETTE);
+    nbt::bytes(&mut file, "Data", BLOCK_DATA);
+    file.extend([0, 0, 0]);
+
+    expected_blocks(&sponge::read(file.as_slice()).unwrap());
+}
+
+#[test]
+fn sponge_rejects_truncated_block_data() {
+    let mut file = Vec::new();
+    nbt::name(&mut file, 10, "Schematic");
+    sponge_header(&mut file, 2);
+    nbt::palette(&mut file, PALETTE);
+    nbt::bytes(&mut file, "BlockData", &BLOCK_DATA[..3]);
+    file.push(0);
+
+    assert!(matches!(
+        sponge::read(file.as_slice()),
+        Err(SchematicError::Malformed(_))
+    ));
+}
+
+#[test]
+fn sponge_rejects_sizes_the_block_data_cannot_fill() {
+    let mut file = Vec::new();
+    nbt::name(&mut file, 10, "Schematic");
+    nbt::int(&mut file, "Version", 2);
+    for side in ["Width", "Height", "Length"] {
+        nbt::short(&mut file, side, -1);
+    }
+    nbt::palette(&mut file, PALETTE);
+    nbt::bytes(&mut file, "BlockData", BLOCK_DATA);
+    file.push(0);
+
+    assert!(matches!(
+        sponge::read(file.as_slice()),
+        Err(SchematicError::Malformed(_))
+    ));
+}
+
+#[test]
+fn nbt_lengths_beyond_the_input_are_refused() {
+    let mut file = Vec::new();
+    nbt::name(&mut file, 10, "Schematic");
+    nbt::name(&mut file, 7, "BlockData");
+    file.extend(i32::MAX.to_be_bytes());
+    file.extend(BLOCK_DATA);
+
+    assert!(matches!(
+        sponge::read(file.as_slice()),
+        Err(SchematicError::Io(_))
+    ));
+}
+
+#[test]
+fn rotation_turns_clockwise_seen_from_above() {
+    let size = UVec3::new(3, 1, 2);
+    let placement = Placement {
+        rotation: Rotation::Clockwise90,
+        ..Placement::default()
+    };
+    assert_eq!(placement.rotated_size(size), UVec3::new(2, 1, 3));
+
+    // -X is west and -Z north: the north-west corner ends up north-east
+    assert_eq!(placement.transform(UVec3::ZERO, size), UVec3::new(1, 0, 0));
+    assert_eq!(
+        placement.transform(UVec3::new(2, 0, 0), size),
+        UVec3::new(1, 0, 2)
+    );
+}
+
+#[test]
+fn every_placement_fills_the_rotated_box() {
+    let size = UVec3::new(3, 2, 4);
+    for rotation in [
+        Rotation::None,
+        Rotation::Clockwise90,
+        Rotation::Clockwise180,
+        Rotation::Clockwise270,
+    ] {
+        for mirror in [Mirror::None, Mirror::X, Mirror::Z] {
+            let placement = Placement {
+                rotation,
+                mirror,
+                skip_air: false,
+            };
+            let rotated = placement.rotated_size(size);
+            let mut seen = Vec::new();
+            for (local, _) in schematic(size, &[BuiltBlockID::Dirt]).iter() {
+                let offset = placement.transform(local, size);
+                assert!(offset.cmplt(rotated).all());
+                assert!(!seen.contains(&offset), "{rotation:?} {mirror:?}");
+                seen.push(offset);
+            }
+        }
+    }
+}
+
+#[test]
+fn mirror_flips_one_axis() {
+    let size = UVec3::new(3, 1, 2);
+    let local = UVec3::new(0, 0, 1);
+    let mirrored = |mirror| {
+        Placement {
+            mirror,
+            ..Placement::default()
+        }
+        .transform(local, size)
+    };
+    assert_eq!(mirrored(Mirror::X), UVec3::new(2, 0, 1));
+    assert_eq!(mirrored(Mirror::Z), UVec3::new(0, 0, 0));
+}
+
+#[test]
+fn skip_air_leaves_out_air() {
+    let schematic = schematic(
+        UVec3::new(2, 1, 1),
+        &[BuiltBlockID::Air, BuiltBlockID::Dirt],
+    );
+    let origin = viewer::chunk::WorldPos { x: 0, y: 0, z: 0 };
+    let placement = Placement {
+        skip_air: true,
+        ..Placement::default()
+    };
+
+    let events: Vec<_> =
+        schematic.set_block_events(origin, placement).collect();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].block, BuiltBlockID::Dirt);
+    assert_eq!(events[0].world_pos.x, 1);
+}
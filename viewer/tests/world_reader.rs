@@ -0,0 +1,392 @@
+use std::sync::mpsc;
+
+use bevy::{ecs::system::RunSystemOnce, prelude::*};
+use viewer::{
+    bindless_material::BindlessMaterial,
+    block::{BlockRegistry, BuiltBlockID},
+    built_block_mesh::{get_texture, isotropic_mesh},
+    chunk::{
+        self, BlockChangeCause, BlockChanged, BlockLookup, BlockMaterial,
+        Chunk, ChunkPos, GetBlockEvent, GetRegionEvent, RegionTooLarge,
+        SetBlockEvent, World, WorldPos, WorldReader,
+    },
+    schematic::{Placement, Schematic},
+};
+
+fn app_with_chunk(chunk_pos: ChunkPos, fill_with: BuiltBlockID) -> App {
+    let missing = isotropic_mesh(get_texture("missing_tile.png"));
+    let mut app = App::new();
+    app.init_resource::<Assets<Mesh>>()
+        .insert_resource(BlockRegistry {
+            grass: missing.clone(),
+            brick: missing.clone(),
+            dirt: missing.clone(),
+            planks_oak: missing.clone(),
+            wool_colored_orange: missing,
+        })
+        .insert_resource(World::new())
+        .insert_resource(BlockMaterial(Handle::default()))
+        .add_event::<GetBlockEvent>()
+        .add_event::<GetRegionEvent>()
+        .add_event::<SetBlockEvent>()
+        .add_event::<BlockChanged>()
+        .add_systems(
+            Update,
+            (
+                // Scheduled like the example app
+                chunk::handle_set_block_events
+                    .run_if(on_event::<SetBlockEvent>),
+                chunk::flush_unspawned_chunks
+                    .after(chunk::handle_set_block_events),
+                chunk::send_block_changed_events
+                    .after(chunk::handle_set_block_events),
+                chunk::handle_get_block_events
+                    .after(chunk::flush_unspawned_chunks),
+                chunk::handle_get_region_events
+                    .after(chunk::flush_unspawned_chunks),
+            ),
+        );
+
+    app.world_mut()
+        .run_system_once(
+            move |mut world: ResMut<World>,
+                  mut commands: Commands,
+                  mut meshes: ResMut<Assets<Mesh>>,
+                  breg: Res<BlockRegistry>| {
+                chunk::spawn_chunk(
+                    &mut world,
+                    &mut commands,
+                    &mut meshes,
+                    &Handle::<BindlessMaterial>::default(),
+                    &breg,
+                    chunk_pos,
+                    Some(fill_with),
+                );
+            },
+        )
+        .unwrap();
+
+    app
+}
+
+fn changes(app: &App) -> Vec<BlockChanged> {
+    app.world()
+        .resource::<Events<BlockChanged>>()
+        .iter_current_update_events()
+        .copied()
+        .collect()
+}
+
+fn chunk_at(app: &App, pos: ChunkPos) -> &Chunk {
+    let entity = app.world().resource::<World>().chunk_entity(pos).unwrap();
+    app.world().get::<Chunk>(entity).unwrap()
+}
+
+fn read(app: &mut App, world_pos: WorldPos) -> BlockLookup {
+    app.world_mut()
+        .run_system_once(move |reader: WorldReader| reader.get_block(world_pos))
+        .unwrap()
+}
+
+#[test]
+fn unloaded_chunk_is_not_air() {
+    let mut app =
+        app_with_chunk(ChunkPos { x: 0, y: 0, z: 0 }, BuiltBlockID::Air);
+
+    assert_eq!(
+        read(&mut app, WorldPos { x: 1, y: 2, z: 3 }),
+        BlockLookup::Loaded(BuiltBlockID::Air)
+    );
+    assert_eq!(
+        read(&mut app, WorldPos { x: -1, y: 2, z: 3 }),
+        BlockLookup::Unloaded
+    );
+}
+
+#[test]
+fn get_block_event_answers_through_sender() {
+    let mut app =
+        app_with_chunk(ChunkPos { x: 0, y: 0, z: 0 }, BuiltBlockID::Brick);
+    let (sender, receiver) = mpsc::channel();
+
+    app.world_mut().send_event_batch([
+        GetBlockEvent {
+            world_pos: WorldPos { x: 0, y: 0, z: 0 },
+            response_sender: Some(sender.clone()),
+        },
+        GetBlockEvent {
+            world_pos: WorldPos { x: 0, y: 100, z: 0 },
+            response_sender: Some(sender),
+        },
+    ]);
+    app.update();
+
+    let responses: Vec<_> = receiver.try_iter().map(|r| r.block).collect();
+    assert_eq!(
+        responses,
+        [
+            BlockLookup::Loaded(BuiltBlockID::Brick),
+            BlockLookup::Unloaded
+        ]
+    );
+}
+
+#[test]
+fn region_read_spans_unloaded_chunks() {
+    let mut app =
+        app_with_chunk(ChunkPos { x: 0, y: 0, z: 0 }, BuiltBlockID::Dirt);
+    let (sender, receiver) = mpsc::channel();
+
+    app.world_mut().send_event(GetRegionEvent {
+        min: WorldPos { x: 9, y: 0, z: 0 },
+        max: WorldPos { x: 6, y: 1, z: 0 },
+        response_sender: Some(sender),
+    });
+    app.update();
+
+    let region = receiver.try_recv().unwrap().unwrap();
+    assert_eq!(region.min, WorldPos { x: 6, y: 0, z: 0 });
+    assert_eq!(region.size, UVec3::new(4, 2, 1));
+    assert_eq!(region.blocks.len(), 8);
+    assert_eq!(
+        region.get(WorldPos { x: 7, y: 1, z: 0 }),
+        Some(BlockLookup::Loaded(BuiltBlockID::Dirt))
+    );
+    assert_eq!(
+        region.get(WorldPos { x: 8, y: 1, z: 0 }),
+        Some(BlockLookup::Unloaded)
+    );
+    assert_eq!(region.get(WorldPos { x: 10, y: 0, z: 0 }), None);
+}
+
+#[test]
+fn oversized_regions_are_refused() {
+    let mut app =
+        app_with_chunk(ChunkPos { x: 0, y: 0, z: 0 }, BuiltBlockID::Dirt);
+    let (sender, receiver) = mpsc::channel();
+
+    let full_range = GetRegionEvent {
+        min: WorldPos {
+            x: i32::MIN,
+            y: i32::MIN,
+            z: i32::MIN,
+        },
+        max: WorldPos {
+            x: i32::MAX,
+            y: i32::MAX,
+            z: i32::MAX,
+        },
+        response_sender: Some(sender.clone()),
+    };
+    // One block past the limit
+    let just_over = GetRegionEvent {
+        min: WorldPos { x: 0, y: 0, z: 0 },
+        max: WorldPos {
+            x: 0,
+            y: 0,
+            z: chunk::MAX_REGION_VOLUME as i32,
+        },
+        response_sender: Some(sender.clone()),
+    };
+    let at_limit = GetRegionEvent {
+        min: WorldPos { x: 0, y: 0, z: 0 },
+        max: WorldPos {
+            x: 0,
+            y: 0,
+            z: chunk::MAX_REGION_VOLUME as i32 - 1,
+        },
+        response_sender: Some(sender),
+    };
+    app.world_mut()
+        .send_event_batch([full_range, just_over, at_limit]);
+    app.update();
+
+    let responses: Vec<_> = receiver.try_iter().collect();
+    assert!(matches!(responses[0], Err(RegionTooLarge { .. })));
+    assert!(matches!(responses[1], Err(RegionTooLarge { .. })));
+    let region = responses[2].as_ref().unwrap();
+    assert_eq!(region.blocks.len() as u64, chunk::MAX_REGION_VOLUME);
+}
+
+#[test]
+fn readers_agree_on_unflushed_blocks() {
+    let mut app =
+        app_with_chunk(ChunkPos { x: 0, y: 0, z: 0 }, BuiltBlockID::Air);
+    let pos = WorldPos { x: 3, y: 4, z: 5 };
+    app.world_mut()
+        .run_system_once(
+            move |mut world: ResMut<World>, mut chunks: Query<&mut Chunk>| {
+                world.set_block_in_chunk(
+                    pos,
+                    BuiltBlockID::Brick,
+                    BlockChangeCause::Unspecified,
+                    &mut chunks,
+                );
+            },
+        )
+        .unwrap();
+
+    // Written to the pending grid, the chunk itself is still air
+    assert_eq!(
+        chunk_at(&app, pos.to_chunk_pos()).get_block(pos),
+        BuiltBlockID::Air
+    );
+    let block = app
+        .world_mut()
+        .run_system_once(move |world: Res<World>, chunks: Query<&mut Chunk>| {
+            world.get_block(pos, &chunks)
+        })
+        .unwrap();
+    assert_eq!(block, BuiltBlockID::Brick);
+    assert_eq!(
+        read(&mut app, pos),
+        BlockLookup::Loaded(BuiltBlockID::Brick)
+    );
+}
+
+#[test]
+fn paste_fills_chunks_it_creates() {
+    let mut app =
+        app_with_chunk(ChunkPos { x: 0, y: 0, z: 0 }, BuiltBlockID::Air);
+    let schematic = Schematic {
+        size: UVec3::new(2, 1, 1),
+        palette: vec![BuiltBlockID::Brick],
+        blocks: vec![0, 0],
+    };
+
+    // Both blocks land in a chunk that doesn't exist yet
+    app.world_mut().send_event_batch(schematic.set_block_events(
+        WorldPos { x: 20, y: 0, z: 0 },
+        Placement::default(),
+    ));
+    app.update();
+
+    // In the chunk itself by the end of the frame, not just pending
+    let chunk = chunk_at(&app, ChunkPos { x: 2, y: 0, z: 0 });
+    assert!(chunk.is_dirty());
+    for x in [20, 21] {
+        assert_eq!(
+            chunk.get_block(WorldPos { x, y: 0, z: 0 }),
+            BuiltBlockID::Brick
+        );
+    }
+
+    // Stays there once no more events arrive
+    app.update();
+    assert_eq!(
+        chunk_at(&app, ChunkPos { x: 2, y: 0, z: 0 }).get_block(WorldPos {
+            x: 21,
+            y: 0,
+            z: 0
+        }),
+        BuiltBlockID::Brick
+    );
+}
+
+#[test]
+fn capture_reads_unloaded_chunks_as_air() {
+    let mut app =
+        app_with_chunk(ChunkPos { x: 0, y: 0, z: 0 }, BuiltBlockID::Brick);
+    let schematic = app
+        .world_mut()
+        .run_system_once(|reader: WorldReader| {
+            Schematic::capture(
+                &reader,
+                WorldPos { x: 1, y: 0, z: 0 },
+                WorldPos { x: -1, y: 0, z: 0 },
+            )
+        })
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(schematic.size, UVec3::new(3, 1, 1));
+    assert_eq!(
+        schematic.iter().map(|(_, block)| block).collect::<Vec<_>>(),
+        [BuiltBlockID::Air, BuiltBlockID::Brick, BuiltBlockID::Brick]
+    );
+}
+
+#[test]
+fn despawned_chunks_drop_pending_blocks() {
+    let mut app =
+        app_with_chunk(ChunkPos { x: 0, y: 0, z: 0 }, BuiltBlockID::Dirt);
+    let entity = app
+        .world()
+        .resource::<World>()
+        .chunk_entity(ChunkPos { x: 0, y: 0, z: 0 })
+        .unwrap();
+    app.world_mut().despawn(entity);
+    app.update();
+
+    let block = app
+        .world_mut()
+        .run_system_once(|world: Res<World>, chunks: Query<&mut Chunk>| {
+            world.get_block(WorldPos { x: 1, y: 1, z: 1 }, &chunks)
+        })
+        .unwrap();
+    assert_eq!(block, BuiltBlockID::Air);
+}
+
+#[test]
+fn set_block_events_report_changes() {
+    let mut app =
+        app_with_chunk(ChunkPos { x: 0, y: 0, z: 0 }, BuiltBlockID::Air);
+    app.world_mut().send_event_batch([
+        SetBlockEvent {
+            world_pos: WorldPos { x: 1, y: 1, z: 1 },
+            block: BuiltBlockID::Dirt,
+            cause: BlockChangeCause::PlayerPlace,
+        },
+        // Chunk created by the event itself
+        SetBlockEvent {
+            world_pos: WorldPos { x: -1, y: 0, z: 0 },
+            block: BuiltBlockID::Brick,
+            cause: BlockChangeCause::Schematic,
+        },
+        // Already air, not a change
+        SetBlockEvent {
+            world_pos: WorldPos { x: 2, y: 2, z: 2 },
+            block: BuiltBlockID::Air,
+            cause: BlockChangeCause::PlayerBreak,
+        },
+    ]);
+    app.update();
+
+    assert_eq!(
+        changes(&app),
+        [
+            BlockChanged {
+                pos: WorldPos { x: 1, y: 1, z: 1 },
+                old: BuiltBlockID::Air,
+                new: BuiltBlockID::Dirt,
+                cause: BlockChangeCause::PlayerPlace,
+            },
+            BlockChanged {
+                pos: WorldPos { x: -1, y: 0, z: 0 },
+                old: BuiltBlockID::Air,
+                new: BuiltBlockID::Brick,
+                cause: BlockChangeCause::Schematic,
+            },
+        ]
+    );
+}
+
+#[test]
+fn filled_chunks_report_their_blocks() {
+    let mut app =
+        app_with_chunk(ChunkPos { x: 0, y: 1, z: 0 }, BuiltBlockID::Grass);
+    app.update();
+
+    let changes = changes(&app);
+    assert_eq!(changes.len(), chunk::SIZE.pow(3));
+    assert!(changes
+        .iter()
+        .all(|change| change.new == BuiltBlockID::Grass
+            && change.cause == BlockChangeCause::Generated));
+    assert!(changes.contains(&BlockChanged {
+        pos: WorldPos { x: 7, y: 15, z: 0 },
+        old: BuiltBlockID::Air,
+        new: BuiltBlockID::Grass,
+        cause: BlockChangeCause::Generated,
+    }));
+}